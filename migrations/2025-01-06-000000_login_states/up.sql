@@ -0,0 +1,12 @@
+-- Pending OAuth logins. A row is created when we hand out an authorize url
+-- and is consumed exactly once when the login is completed.
+CREATE TABLE login_states (
+    state TEXT NOT NULL PRIMARY KEY,
+    pkce_verifier TEXT NOT NULL,
+    redirect_uri TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX ON login_states (expires_at);
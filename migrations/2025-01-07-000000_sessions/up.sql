@@ -0,0 +1,11 @@
+-- Every session token we issue has a row here, keyed by the `jti` claim, so
+-- that tokens can be revoked before they expire.
+CREATE TABLE sessions (
+    id TEXT NOT NULL PRIMARY KEY,
+    twitch_user_id INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX ON sessions (twitch_user_id);
@@ -0,0 +1,10 @@
+-- Roles are hierarchical, a user only ever has the highest role they were granted.
+CREATE TYPE user_role AS ENUM ('owner', 'admin', 'reviewer', 'viewer');
+
+CREATE TABLE user_roles (
+    twitch_user_id INT NOT NULL PRIMARY KEY,
+    role user_role NOT NULL,
+    granted_by INT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
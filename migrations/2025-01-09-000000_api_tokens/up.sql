@@ -0,0 +1,19 @@
+-- Long lived tokens for bots and automation. Only a hash of the token is stored,
+-- the token itself is shown once when it is created. The owner columns mirror the
+-- user the token acts on behalf of.
+CREATE TABLE api_tokens (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL CHECK (LENGTH(name) <= 100),
+    token_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL,
+    twitch_user_id INT NOT NULL,
+    twitch_username TEXT NOT NULL,
+    twitch_display_name TEXT NOT NULL,
+    twitch_profile_image_url TEXT NOT NULL,
+    twitch_account_type twitch_account_type NOT NULL,
+    follow_count INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,10 @@
+-- A record of sensitive actions taken by staff.
+CREATE TABLE audit_log (
+    id SERIAL PRIMARY KEY,
+    actor_twitch_user_id INT NOT NULL,
+    action TEXT NOT NULL,
+    target_twitch_user_id INT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON audit_log (created_at);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS applications_twitch_username_idx;
+ALTER TABLE application_comments DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE applications DROP COLUMN IF EXISTS search_vector;
@@ -0,0 +1,18 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Usernames are weighted above the reason so searching for a streamer ranks
+-- their own application first.
+ALTER TABLE applications ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', twitch_username || ' ' || twitch_display_name), 'A') ||
+    setweight(to_tsvector('english', reason), 'B')
+) STORED;
+
+ALTER TABLE application_comments ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    to_tsvector('english', comment)
+) STORED;
+
+CREATE INDEX ON applications USING GIN (search_vector);
+CREATE INDEX ON application_comments USING GIN (search_vector);
+
+-- Speeds up the substring matches on usernames.
+CREATE INDEX ON applications USING GIN (twitch_username gin_trgm_ops);
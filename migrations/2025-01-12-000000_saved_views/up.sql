@@ -0,0 +1,14 @@
+CREATE TABLE saved_views (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    twitch_user_id INTEGER NOT NULL,
+    -- Shared views are visible to everyone who can read applications.
+    shared BOOLEAN NOT NULL DEFAULT FALSE,
+    -- The filters and sort of the view, as accepted by GET /applications.
+    query JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON saved_views (twitch_user_id);
+CREATE INDEX ON saved_views (shared) WHERE shared;
@@ -0,0 +1,12 @@
+-- Postgres can not drop enum values, so recreate the type without it.
+UPDATE applications SET status = 'pending', completed_at = NULL WHERE status = 'withdrawn';
+
+ALTER TYPE application_status RENAME TO application_status_old;
+CREATE TYPE application_status AS ENUM ('pending', 'approved', 'maybe', 'rejected', 'in');
+
+ALTER TABLE applications
+    ALTER COLUMN status DROP DEFAULT,
+    ALTER COLUMN status TYPE application_status USING status::TEXT::application_status,
+    ALTER COLUMN status SET DEFAULT 'pending';
+
+DROP TYPE application_status_old;
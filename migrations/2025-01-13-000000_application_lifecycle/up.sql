@@ -0,0 +1,4 @@
+ALTER TYPE application_status ADD VALUE IF NOT EXISTS 'withdrawn';
+
+-- Decisions made before completed_at was maintained.
+UPDATE applications SET completed_at = updated_at WHERE status IN ('approved', 'rejected', 'in') AND completed_at IS NULL;
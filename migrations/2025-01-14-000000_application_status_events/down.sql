@@ -0,0 +1,12 @@
+INSERT INTO application_comments (application_id, comment, twitch_user_id, twitch_username, twitch_display_name, twitch_profile_image_url, created_at)
+SELECT
+    application_id,
+    CASE to_status WHEN 'approved' THEN 'Moved to accepted' ELSE 'Moved to ' || to_status::TEXT END,
+    actor_twitch_user_id,
+    actor_twitch_username,
+    actor_twitch_username,
+    '',
+    created_at
+FROM application_status_events;
+
+DROP TABLE IF EXISTS application_status_events;
@@ -0,0 +1,44 @@
+CREATE TABLE application_status_events (
+    id SERIAL PRIMARY KEY,
+    application_id INTEGER NOT NULL REFERENCES applications(id) ON DELETE CASCADE,
+    from_status application_status NOT NULL,
+    to_status application_status NOT NULL,
+    actor_twitch_user_id INTEGER NOT NULL,
+    actor_twitch_username TEXT NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON application_status_events (application_id, created_at);
+
+-- Status changes used to be recorded as comments, move them over. Every
+-- application starts out pending, so the first change is from pending.
+INSERT INTO application_status_events (application_id, from_status, to_status, actor_twitch_user_id, actor_twitch_username, created_at)
+SELECT
+    application_id,
+    COALESCE(LAG(to_status) OVER (PARTITION BY application_id ORDER BY created_at, id), 'pending'),
+    to_status,
+    twitch_user_id,
+    twitch_username,
+    created_at
+FROM (
+    SELECT *, CASE comment
+        WHEN 'Moved to accepted' THEN 'approved'
+        WHEN 'Moved to rejected' THEN 'rejected'
+        WHEN 'Moved to maybe' THEN 'maybe'
+        WHEN 'Moved to pending' THEN 'pending'
+        WHEN 'Moved to in' THEN 'in'
+        WHEN 'Moved to withdrawn' THEN 'withdrawn'
+    END::application_status AS to_status
+    FROM application_comments
+) AS moves
+WHERE to_status IS NOT NULL;
+
+DELETE FROM application_comments WHERE comment IN (
+    'Moved to accepted',
+    'Moved to rejected',
+    'Moved to maybe',
+    'Moved to pending',
+    'Moved to in',
+    'Moved to withdrawn'
+);
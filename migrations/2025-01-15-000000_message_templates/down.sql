@@ -0,0 +1,2 @@
+ALTER TABLE application_status_events DROP COLUMN IF EXISTS reason_code;
+DROP TABLE IF EXISTS message_templates;
@@ -0,0 +1,13 @@
+-- Reusable messages sent to applicants when deciding their application, the
+-- code doubles as the reason code of the decision.
+CREATE TABLE message_templates (
+    id SERIAL PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_by INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE application_status_events ADD COLUMN reason_code TEXT;
@@ -0,0 +1,4 @@
+-- Internal comments are only shown to staff, never to the applicant.
+CREATE TYPE comment_visibility AS ENUM ('public', 'internal');
+
+ALTER TABLE application_comments ADD COLUMN visibility comment_visibility NOT NULL DEFAULT 'public';
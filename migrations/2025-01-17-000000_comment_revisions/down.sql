@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS comment_revisions;
+
+ALTER TABLE application_comments
+    DROP COLUMN IF EXISTS edited_at,
+    DROP COLUMN IF EXISTS deleted_at,
+    DROP COLUMN IF EXISTS deleted_by;
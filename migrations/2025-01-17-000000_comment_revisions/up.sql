@@ -0,0 +1,17 @@
+-- Deleted comments stay behind as a tombstone with their text cleared.
+ALTER TABLE application_comments
+    ADD COLUMN edited_at TIMESTAMPTZ,
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN deleted_by INT;
+
+-- The text of a comment before each edit or deletion, and who replaced it.
+CREATE TABLE comment_revisions (
+    id SERIAL PRIMARY KEY,
+    comment_id INT NOT NULL REFERENCES application_comments(id) ON DELETE CASCADE,
+    comment TEXT NOT NULL,
+    twitch_user_id INT NOT NULL,
+    twitch_username TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX ON comment_revisions (comment_id, created_at);
@@ -0,0 +1,3 @@
+ALTER TABLE user_roles DROP COLUMN IF EXISTS twitch_username;
+DROP TABLE IF EXISTS comment_mentions;
+ALTER TABLE application_comments DROP COLUMN IF EXISTS parent_id;
@@ -0,0 +1,32 @@
+-- Replies to a top level comment, threads are only one level deep.
+ALTER TABLE application_comments ADD COLUMN parent_id INT REFERENCES application_comments(id) ON DELETE CASCADE;
+
+CREATE INDEX ON application_comments (parent_id);
+
+-- Staff that were mentioned with @username in a comment.
+CREATE TABLE comment_mentions (
+    comment_id INT NOT NULL REFERENCES application_comments(id) ON DELETE CASCADE,
+    twitch_user_id INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (comment_id, twitch_user_id)
+);
+
+CREATE INDEX ON comment_mentions (twitch_user_id, created_at);
+
+-- Mentions are resolved against the usernames of staff, which are recorded
+-- whenever they log in.
+ALTER TABLE user_roles ADD COLUMN twitch_username TEXT;
+
+UPDATE user_roles r SET twitch_username = (
+    SELECT twitch_username FROM (
+        SELECT twitch_username, created_at FROM application_comments WHERE twitch_user_id = r.twitch_user_id
+        UNION ALL
+        SELECT actor_twitch_username, created_at FROM application_status_events WHERE actor_twitch_user_id = r.twitch_user_id
+        UNION ALL
+        SELECT twitch_username, created_at FROM api_tokens WHERE twitch_user_id = r.twitch_user_id
+    ) AS seen
+    ORDER BY created_at DESC
+    LIMIT 1
+);
+
+CREATE INDEX ON user_roles (twitch_username);
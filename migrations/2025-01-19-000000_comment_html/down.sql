@@ -0,0 +1 @@
+ALTER TABLE application_comments DROP COLUMN IF EXISTS comment_html;
@@ -0,0 +1,13 @@
+-- Comments are written in Markdown and rendered to HTML when they are saved.
+ALTER TABLE application_comments ADD COLUMN comment_html TEXT NOT NULL DEFAULT '';
+
+-- Earlier comments were plain text, so show them escaped with their line breaks.
+UPDATE application_comments
+SET comment_html = '<p>' || replace(
+    replace(replace(replace(replace(replace(comment, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;'),
+    E'\n',
+    '<br>'
+) || '</p>'
+WHERE comment <> '';
+
+ALTER TABLE application_comments ALTER COLUMN comment_html DROP DEFAULT;
@@ -1,2 +1,778 @@
 // @generated automatically by Diesel CLI.
 
+/// A module containing custom SQL type definitions
+///
+/// (Automatically generated by Diesel.)
+pub mod sql_types {
+    /// The `application_status` SQL type
+    ///
+    /// (Automatically generated by Diesel.)
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "application_status"))]
+    pub struct ApplicationStatus;
+
+    /// The `comment_visibility` SQL type
+    ///
+    /// (Automatically generated by Diesel.)
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "comment_visibility"))]
+    pub struct CommentVisibility;
+
+    /// The `pg_catalog.tsvector` SQL type
+    ///
+    /// (Automatically generated by Diesel.)
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "tsvector", schema = "pg_catalog"))]
+    pub struct Tsvector;
+
+    /// The `twitch_account_type` SQL type
+    ///
+    /// (Automatically generated by Diesel.)
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "twitch_account_type"))]
+    pub struct TwitchAccountType;
+
+    /// The `user_role` SQL type
+    ///
+    /// (Automatically generated by Diesel.)
+    #[derive(diesel::query_builder::QueryId, Clone, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "user_role"))]
+    pub struct UserRole;
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TwitchAccountType;
+
+    /// Representation of the `api_tokens` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    api_tokens (id) {
+        /// The `id` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `name` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Text,
+        /// The `token_hash` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        token_hash -> Text,
+        /// The `scopes` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Array<Nullable<Text>>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        scopes -> Array<Nullable<Text>>,
+        /// The `twitch_user_id` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_user_id -> Int4,
+        /// The `twitch_username` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_username -> Text,
+        /// The `twitch_display_name` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_display_name -> Text,
+        /// The `twitch_profile_image_url` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_profile_image_url -> Text,
+        /// The `twitch_account_type` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `TwitchAccountType`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_account_type -> TwitchAccountType,
+        /// The `follow_count` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        follow_count -> Int4,
+        /// The `created_at` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `expires_at` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expires_at -> Nullable<Timestamptz>,
+        /// The `last_used_at` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_used_at -> Nullable<Timestamptz>,
+        /// The `revoked_at` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        revoked_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Tsvector;
+    use super::sql_types::CommentVisibility;
+
+    /// Representation of the `application_comments` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    application_comments (id) {
+        /// The `id` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `application_id` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        application_id -> Int4,
+        /// The `comment` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        comment -> Text,
+        /// The `twitch_user_id` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_user_id -> Int4,
+        /// The `twitch_username` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_username -> Text,
+        /// The `twitch_display_name` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_display_name -> Text,
+        /// The `twitch_profile_image_url` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_profile_image_url -> Text,
+        /// The `created_at` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `search_vector` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Nullable<Tsvector>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        search_vector -> Nullable<Tsvector>,
+        /// The `visibility` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `CommentVisibility`.
+        ///
+        /// (Automatically generated by Diesel.)
+        visibility -> CommentVisibility,
+        /// The `edited_at` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        edited_at -> Nullable<Timestamptz>,
+        /// The `deleted_at` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deleted_at -> Nullable<Timestamptz>,
+        /// The `deleted_by` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        deleted_by -> Nullable<Int4>,
+        /// The `parent_id` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        parent_id -> Nullable<Int4>,
+        /// The `comment_html` column of the `application_comments` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        comment_html -> Text,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ApplicationStatus;
+
+    /// Representation of the `application_status_events` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    application_status_events (id) {
+        /// The `id` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `application_id` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        application_id -> Int4,
+        /// The `from_status` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `ApplicationStatus`.
+        ///
+        /// (Automatically generated by Diesel.)
+        from_status -> ApplicationStatus,
+        /// The `to_status` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `ApplicationStatus`.
+        ///
+        /// (Automatically generated by Diesel.)
+        to_status -> ApplicationStatus,
+        /// The `actor_twitch_user_id` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        actor_twitch_user_id -> Int4,
+        /// The `actor_twitch_username` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        actor_twitch_username -> Text,
+        /// The `reason` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        reason -> Nullable<Text>,
+        /// The `created_at` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `reason_code` column of the `application_status_events` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        reason_code -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TwitchAccountType;
+    use super::sql_types::ApplicationStatus;
+    use super::sql_types::Tsvector;
+
+    /// Representation of the `applications` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    applications (id) {
+        /// The `id` column of the `applications` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `twitch_id` column of the `applications` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_id -> Int4,
+        /// The `twitch_username` column of the `applications` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_username -> Text,
+        /// The `twitch_display_name` column of the `applications` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_display_name -> Text,
+        /// The `twitch_profile_image_url` column of the `applications` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_profile_image_url -> Text,
+        /// The `twitch_account_type` column of the `applications` table.
+        ///
+        /// Its SQL type is `TwitchAccountType`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_account_type -> TwitchAccountType,
+        /// The `status` column of the `applications` table.
+        ///
+        /// Its SQL type is `ApplicationStatus`.
+        ///
+        /// (Automatically generated by Diesel.)
+        status -> ApplicationStatus,
+        /// The `reason` column of the `applications` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        reason -> Text,
+        /// The `support_clip_url` column of the `applications` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        support_clip_url -> Text,
+        /// The `follow_count` column of the `applications` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        follow_count -> Int4,
+        /// The `created_at` column of the `applications` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `updated_at` column of the `applications` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+        /// The `completed_at` column of the `applications` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        completed_at -> Nullable<Timestamptz>,
+        /// The `search_vector` column of the `applications` table.
+        ///
+        /// Its SQL type is `Nullable<Tsvector>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        search_vector -> Nullable<Tsvector>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `audit_log` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    audit_log (id) {
+        /// The `id` column of the `audit_log` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `actor_twitch_user_id` column of the `audit_log` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        actor_twitch_user_id -> Int4,
+        /// The `action` column of the `audit_log` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        action -> Text,
+        /// The `target_twitch_user_id` column of the `audit_log` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        target_twitch_user_id -> Nullable<Int4>,
+        /// The `created_at` column of the `audit_log` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `comment_mentions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    comment_mentions (comment_id, twitch_user_id) {
+        /// The `comment_id` column of the `comment_mentions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        comment_id -> Int4,
+        /// The `twitch_user_id` column of the `comment_mentions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_user_id -> Int4,
+        /// The `created_at` column of the `comment_mentions` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `comment_revisions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    comment_revisions (id) {
+        /// The `id` column of the `comment_revisions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `comment_id` column of the `comment_revisions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        comment_id -> Int4,
+        /// The `comment` column of the `comment_revisions` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        comment -> Text,
+        /// The `twitch_user_id` column of the `comment_revisions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_user_id -> Int4,
+        /// The `twitch_username` column of the `comment_revisions` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_username -> Text,
+        /// The `created_at` column of the `comment_revisions` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `health_check` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    health_check (id) {
+        /// The `id` column of the `health_check` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `updated_at` column of the `health_check` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `login_states` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    login_states (state) {
+        /// The `state` column of the `login_states` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        state -> Text,
+        /// The `pkce_verifier` column of the `login_states` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        pkce_verifier -> Text,
+        /// The `redirect_uri` column of the `login_states` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        redirect_uri -> Text,
+        /// The `created_at` column of the `login_states` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `expires_at` column of the `login_states` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expires_at -> Timestamptz,
+        /// The `used_at` column of the `login_states` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        used_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `message_templates` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    message_templates (id) {
+        /// The `id` column of the `message_templates` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `code` column of the `message_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        code -> Text,
+        /// The `name` column of the `message_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Text,
+        /// The `body` column of the `message_templates` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        body -> Text,
+        /// The `created_by` column of the `message_templates` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_by -> Int4,
+        /// The `created_at` column of the `message_templates` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `updated_at` column of the `message_templates` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `saved_views` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    saved_views (id) {
+        /// The `id` column of the `saved_views` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Int4,
+        /// The `name` column of the `saved_views` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Text,
+        /// The `twitch_user_id` column of the `saved_views` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_user_id -> Int4,
+        /// The `shared` column of the `saved_views` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        shared -> Bool,
+        /// The `query` column of the `saved_views` table.
+        ///
+        /// Its SQL type is `Jsonb`.
+        ///
+        /// (Automatically generated by Diesel.)
+        query -> Jsonb,
+        /// The `created_at` column of the `saved_views` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `updated_at` column of the `saved_views` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `sessions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    sessions (id) {
+        /// The `id` column of the `sessions` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Text,
+        /// The `twitch_user_id` column of the `sessions` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_user_id -> Int4,
+        /// The `created_at` column of the `sessions` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `expires_at` column of the `sessions` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expires_at -> Timestamptz,
+        /// The `revoked_at` column of the `sessions` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamptz>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        revoked_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::UserRole;
+
+    /// Representation of the `user_roles` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    user_roles (twitch_user_id) {
+        /// The `twitch_user_id` column of the `user_roles` table.
+        ///
+        /// Its SQL type is `Int4`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_user_id -> Int4,
+        /// The `role` column of the `user_roles` table.
+        ///
+        /// Its SQL type is `UserRole`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role -> UserRole,
+        /// The `granted_by` column of the `user_roles` table.
+        ///
+        /// Its SQL type is `Nullable<Int4>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        granted_by -> Nullable<Int4>,
+        /// The `created_at` column of the `user_roles` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created_at -> Timestamptz,
+        /// The `updated_at` column of the `user_roles` table.
+        ///
+        /// Its SQL type is `Timestamptz`.
+        ///
+        /// (Automatically generated by Diesel.)
+        updated_at -> Timestamptz,
+        /// The `twitch_username` column of the `user_roles` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        twitch_username -> Nullable<Text>,
+    }
+}
+
+diesel::joinable!(application_comments -> applications (application_id));
+diesel::joinable!(application_status_events -> applications (application_id));
+diesel::joinable!(comment_mentions -> application_comments (comment_id));
+diesel::joinable!(comment_revisions -> application_comments (comment_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    api_tokens,
+    application_comments,
+    application_status_events,
+    applications,
+    audit_log,
+    comment_mentions,
+    comment_revisions,
+    health_check,
+    login_states,
+    message_templates,
+    saved_views,
+    sessions,
+    user_roles,
+);
@@ -0,0 +1,303 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use diesel::prelude::Insertable;
+use diesel::query_dsl::methods::{FindDsl, OrderDsl, SelectDsl};
+use diesel::upsert::excluded;
+use diesel::{ExpressionMethods, OptionalExtension, SelectableHelper};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+use super::auth::{revoke_user_sessions, user_role, RequirePermission, User};
+use super::error::ApiError;
+use super::permissions::scopes;
+use crate::database::enums::UserRole;
+use crate::database::schema;
+use crate::database::types::{Application, UserRoleGrant};
+use crate::global::Global;
+
+pub fn routes() -> Router<Arc<Global>> {
+    Router::new()
+        .route("/roles", get(get_roles))
+        .route("/users/:twitch_id/role", put(grant_role).delete(revoke_role))
+        .route("/users/:twitch_id/sessions/revoke", post(revoke_sessions))
+        .route("/users/:twitch_id/impersonate", post(impersonate))
+}
+
+#[derive(Insertable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::audit_log)]
+struct InsertAuditLog<'a> {
+    actor_twitch_user_id: i32,
+    action: &'a str,
+    target_twitch_user_id: Option<i32>,
+}
+
+/// Only owners may hand out or take away the admin and owner roles, admins
+/// can manage everyone below them.
+fn can_manage(actor: UserRole, role: UserRole) -> bool {
+    actor == UserRole::Owner || (actor.includes(role) && actor != role)
+}
+
+/// GET /admin/roles
+/// Get every user that has a role
+/// Scope: roles:manage
+async fn get_roles(
+    State(global): State<Arc<Global>>,
+    _: RequirePermission<scopes::RolesManage>,
+) -> Result<Json<Vec<UserRoleGrant>>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let roles = schema::user_roles::table
+        .order(schema::user_roles::dsl::created_at)
+        .select(UserRoleGrant::as_select())
+        .load(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch roles: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(roles))
+}
+
+#[derive(serde::Deserialize)]
+struct GrantRoleRequest {
+    role: UserRole,
+    /// Their Twitch login, so they can be @mentioned before they log in.
+    twitch_username: Option<String>,
+}
+
+/// The last username we have seen a user use, from their role or else their
+/// application.
+async fn known_username(conn: &mut AsyncPgConnection, twitch_id: i32) -> anyhow::Result<Option<String>> {
+    let username = schema::user_roles::dsl::user_roles
+        .find(twitch_id)
+        .select(schema::user_roles::dsl::twitch_username)
+        .get_result::<Option<String>>(conn)
+        .await
+        .optional()?
+        .flatten();
+
+    match username {
+        Some(username) => Ok(Some(username)),
+        None => Ok(Application::fetch_by_twitch_id(conn, twitch_id)
+            .await?
+            .map(|application| application.twitch_username)),
+    }
+}
+
+/// PUT /admin/users/:twitch_id/role
+/// Grant a role to a user, replacing any role they had before
+/// Scope: roles:manage
+async fn grant_role(
+    State(global): State<Arc<Global>>,
+    Path(twitch_id): Path<i32>,
+    RequirePermission(user, actor_role, _): RequirePermission<scopes::RolesManage>,
+    Json(body): Json<GrantRoleRequest>,
+) -> Result<Json<UserRoleGrant>, ApiError> {
+    if !can_manage(actor_role, body.role) {
+        return Err(ApiError::forbidden("you can not grant this role"));
+    }
+
+    if body.twitch_username.as_ref().is_some_and(|username| {
+        !(1..=25).contains(&username.len()) || !username.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+    }) {
+        return Err(ApiError::bad_request("invalid twitch username"));
+    }
+
+    if let Some(current) = user_role(&global, twitch_id).await? {
+        if !can_manage(actor_role, current) {
+            return Err(ApiError::forbidden("you can not change the role of this user"));
+        }
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let twitch_username = match body.twitch_username {
+        Some(username) => Some(username.to_ascii_lowercase()),
+        None => known_username(&mut db, twitch_id).await.map_err(|err| {
+            tracing::error!("Failed to fetch username: {err:#}");
+            ApiError::internal_server_error()
+        })?,
+    };
+
+    let grant = diesel::insert_into(schema::user_roles::table)
+        .values((
+            schema::user_roles::dsl::twitch_user_id.eq(twitch_id),
+            schema::user_roles::dsl::role.eq(body.role),
+            schema::user_roles::dsl::granted_by.eq(user.twitch_user_id),
+            schema::user_roles::dsl::twitch_username.eq(twitch_username),
+        ))
+        .on_conflict(schema::user_roles::dsl::twitch_user_id)
+        .do_update()
+        .set((
+            schema::user_roles::dsl::role.eq(excluded(schema::user_roles::dsl::role)),
+            schema::user_roles::dsl::granted_by.eq(excluded(schema::user_roles::dsl::granted_by)),
+            schema::user_roles::dsl::twitch_username.eq(excluded(schema::user_roles::dsl::twitch_username)),
+            schema::user_roles::dsl::updated_at.eq(chrono::Utc::now()),
+        ))
+        .returning(UserRoleGrant::as_returning())
+        .get_result(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to grant role: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    global.role_cache.remove(&twitch_id);
+
+    Ok(Json(grant))
+}
+
+#[derive(serde::Serialize)]
+struct RevokeRoleResponse {
+    role: Option<UserRole>,
+}
+
+/// DELETE /admin/users/:twitch_id/role
+/// Take away the role of a user
+/// Scope: roles:manage
+async fn revoke_role(
+    State(global): State<Arc<Global>>,
+    Path(twitch_id): Path<i32>,
+    RequirePermission(_, actor_role, _): RequirePermission<scopes::RolesManage>,
+) -> Result<Json<RevokeRoleResponse>, ApiError> {
+    if let Some(current) = user_role(&global, twitch_id).await? {
+        if !can_manage(actor_role, current) {
+            return Err(ApiError::forbidden("you can not change the role of this user"));
+        }
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let role = diesel::delete(schema::user_roles::dsl::user_roles.find(twitch_id))
+        .returning(schema::user_roles::dsl::role)
+        .get_result(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to revoke role: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    global.role_cache.remove(&twitch_id);
+
+    Ok(Json(RevokeRoleResponse { role }))
+}
+
+#[derive(serde::Serialize)]
+struct RevokeSessionsResponse {
+    revoked: usize,
+}
+
+/// POST /admin/users/:twitch_id/sessions/revoke
+/// Revoke every session of a user
+/// Scope: sessions:manage
+async fn revoke_sessions(
+    State(global): State<Arc<Global>>,
+    Path(twitch_id): Path<i32>,
+    _: RequirePermission<scopes::SessionsManage>,
+) -> Result<Json<RevokeSessionsResponse>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let revoked = revoke_user_sessions(&global, &mut db, twitch_id).await.map_err(|err| {
+        tracing::error!("Failed to revoke sessions: {err:#}");
+        ApiError::internal_server_error()
+    })?;
+
+    Ok(Json(RevokeSessionsResponse { revoked }))
+}
+
+#[derive(serde::Serialize)]
+struct ImpersonateResponse {
+    token: String,
+    user: User,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// POST /admin/users/:twitch_id/impersonate
+/// Get a short-lived, read-only token to view the api as an applicant
+/// Scope: users:impersonate
+async fn impersonate(
+    State(global): State<Arc<Global>>,
+    Path(twitch_id): Path<i32>,
+    RequirePermission(admin, actor_role, _): RequirePermission<scopes::UsersImpersonate>,
+) -> Result<Json<ImpersonateResponse>, ApiError> {
+    if let Some(current) = user_role(&global, twitch_id).await? {
+        if !can_manage(actor_role, current) {
+            return Err(ApiError::forbidden("you can not impersonate this user"));
+        }
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    // We only know who a user is from their application.
+    let application = Application::fetch_by_twitch_id(&mut db, twitch_id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch application: {err}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(ApiError::not_found)?;
+
+    let user = User {
+        twitch_user_id: application.twitch_id,
+        twitch_username: application.twitch_username,
+        twitch_display_name: application.twitch_display_name,
+        twitch_profile_image_url: application.twitch_profile_image_url,
+        twitch_account_type: application.twitch_account_type,
+        follow_count: application.follow_count,
+        api_token_scopes: None,
+    };
+
+    let token = db
+        .transaction({
+            let (global, user) = (global.clone(), user.clone());
+            move |conn| {
+                async move {
+                    let token = user.issue_impersonation_token(&global, conn, &admin).await?;
+
+                    diesel::insert_into(schema::audit_log::table)
+                        .values(InsertAuditLog {
+                            actor_twitch_user_id: admin.twitch_user_id,
+                            action: "impersonate",
+                            target_twitch_user_id: Some(twitch_id),
+                        })
+                        .execute(conn)
+                        .await?;
+
+                    anyhow::Ok(token)
+                }
+                .scope_boxed()
+            }
+        })
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to impersonate user: {err:#}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(ImpersonateResponse {
+        token,
+        user,
+        expires_at: chrono::Utc::now() + chrono::Duration::seconds(global.config.impersonation_lifetime_secs),
+    }))
+}
@@ -1,36 +1,66 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
-use axum::routing::{get, post};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::routing::{get, patch, post};
 use axum::{Json, Router};
+use chrono::{DateTime, SubsecRound, Utc};
 use diesel::prelude::Insertable;
-use diesel::query_dsl::methods::{FilterDsl, FindDsl, SelectDsl};
-use diesel::{ExpressionMethods, SelectableHelper};
-use diesel_async::RunQueryDsl;
+use diesel::query_dsl::methods::{FilterDsl, FindDsl, LimitDsl, OrderDsl, SelectDsl};
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, SelectableHelper};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 
-use super::auth::{TwitchAdminUser, TwitchUser};
+use super::applications::{comment_count, decode_cursor, encode_cursor, ApplicationResult, PageRequest};
+use super::auth::{has_permission, RequirePermission, TwitchUser, User};
 use super::error::ApiError;
-use crate::database::enums::ApplicationStatus;
+use super::permissions::{scopes, Permission};
+use super::{markdown, mentions, templates};
+use crate::database::enums::{ApplicationStatus, CommentVisibility};
 use crate::database::schema;
-use crate::database::types::{Application, ApplicationComment};
+use crate::database::types::{Application, ApplicationComment, ApplicationStatusEvent, CommentRevision};
 use crate::global::Global;
 
 pub fn routes() -> Router<Arc<Global>> {
     Router::new()
         .route("/:id", get(get_application))
         .route("/:id", post(update_application))
+        .route("/:id/withdraw", post(withdraw_application))
+        .route("/:id/history", get(get_history))
         .route("/:id/comment", post(add_comment))
         .route("/:id/comments", get(get_comments))
+        .route("/:id/comments/:comment_id", patch(edit_comment).delete(delete_comment))
+        .route("/:id/comments/:comment_id/revisions", get(get_comment_revisions))
+}
+
+type WithEtag<T> = ([(header::HeaderName, String); 1], Json<T>);
+
+/// The version of an application, it changes whenever the application is
+/// updated.
+fn etag(application: &Application) -> String {
+    format!("\"{}\"", application.updated_at.timestamp_micros())
+}
+
+/// Whether an `If-Match` header matches the current version of an application.
+fn if_match(header: &HeaderValue, application: &Application) -> bool {
+    let etag = etag(application);
+    header
+        .to_str()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag)
 }
 
 /// GET /applications/:id
-/// Get an application by id
-/// Scope: user (own application) or admin (any application)
+/// Get an application by id, its ETag can be sent back as If-Match when
+/// updating it
+/// Scope: user (own application) or applications:read (any application)
 async fn get_application(
     State(global): State<Arc<Global>>,
     Path(id): Path<i32>,
     TwitchUser(user): TwitchUser,
-) -> Result<Json<Application>, ApiError> {
+) -> Result<WithEtag<ApplicationResult>, ApiError> {
     let mut db = global.database.get().await.map_err(|err| {
         tracing::error!("Failed to get database: {err}");
         ApiError::internal_server_error()
@@ -44,16 +74,47 @@ async fn get_application(
         })?
         .ok_or_else(ApiError::not_found)?;
 
-    if application.twitch_id != user.twitch_user_id && !global.config.admin_twitch_ids.contains(&user.twitch_user_id) {
+    let is_applicant = application.twitch_id == user.twitch_user_id;
+    if !is_applicant && !has_permission(&global, &user, Permission::ApplicationsRead).await? {
         return Err(ApiError::not_found());
     }
 
-    Ok(Json(application))
+    let comment_count = schema::applications::table
+        .find(id)
+        .select(comment_count(!is_applicant))
+        .get_result::<Option<i64>>(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to count comments: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok((
+        [(header::ETAG, etag(&application))],
+        Json(ApplicationResult {
+            application,
+            comment_count: comment_count.unwrap_or_default(),
+            snippets: None,
+        }),
+    ))
 }
 
 #[derive(serde::Deserialize)]
 struct UpdateApplicationRequest {
     status: ApplicationStatus,
+    reason: Option<String>,
+    /// The code of a message template to send the applicant.
+    reason_code: Option<String>,
+    /// Sent to the applicant instead of the template of the reason code.
+    message: Option<String>,
+    /// The `updated_at` the client last saw, for clients that can not send
+    /// an If-Match header.
+    expected_updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Deserialize)]
+struct WithdrawApplicationRequest {
+    reason: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -64,29 +125,187 @@ struct UpdateApplicationResponse {
 #[derive(Insertable)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[diesel(table_name = schema::application_comments)]
-struct InsertComment<'a> {
+pub struct InsertComment<'a> {
+    pub application_id: i32,
+    pub comment: &'a str,
+    /// Rendered from the comment with [`markdown::render`].
+    pub comment_html: &'a str,
+    pub twitch_user_id: i32,
+    pub twitch_username: &'a str,
+    pub twitch_display_name: &'a str,
+    pub twitch_profile_image_url: &'a str,
+    pub visibility: CommentVisibility,
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::application_status_events)]
+struct InsertStatusEvent<'a> {
     application_id: i32,
-    comment: &'a str,
-    twitch_user_id: i32,
-    twitch_username: &'a str,
-    twitch_display_name: &'a str,
-    twitch_profile_image_url: &'a str,
+    from_status: ApplicationStatus,
+    to_status: ApplicationStatus,
+    actor_twitch_user_id: i32,
+    actor_twitch_username: &'a str,
+    reason: Option<&'a str>,
+    reason_code: Option<&'a str>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Why an application is moved and what to tell its applicant.
+#[derive(Default, Clone)]
+pub struct Decision {
+    /// Kept in the status history.
+    pub reason: Option<String>,
+    pub reason_code: Option<String>,
+    /// Posted to the comment thread of the application for the applicant after
+    /// filling in its placeholders, see [`templates::render`].
+    pub message: Option<String>,
+}
+
+/// Move an application to another status, enforcing the lifecycle of
+/// applications and recording the change in its status history. Returns
+/// `None` if the application changed since it was read.
+pub async fn transition(
+    db: &mut AsyncPgConnection,
+    application: &Application,
+    status: ApplicationStatus,
+    user: &User,
+    decision: Decision,
+) -> Result<Option<Application>, ApiError> {
+    if decision.reason.as_ref().is_some_and(|reason| reason.len() > 1000) {
+        return Err(ApiError::bad_request("reason too long"));
+    }
+
+    if !application.status.can_transition_to(status) {
+        return Err(
+            ApiError::conflict(format!("can not move an application from {} to {status}", application.status))
+                .with_data(application),
+        );
+    }
+
+    // Postgres keeps microseconds, truncate so the ETag we hand out matches
+    // what is stored.
+    let now = chrono::Utc::now().trunc_subsecs(6);
+    // Keep the original completion time when moving between finished states,
+    // e.g. from approved to in.
+    let completed_at = status.is_completed().then(|| application.completed_at.unwrap_or(now));
+
+    let message = decision
+        .message
+        .map(|message| templates::render(&message, application, status, user))
+        .transpose()?;
+
+    db.transaction({
+        let (id, from, updated_at, user) = (application.id, application.status, application.updated_at, user.clone());
+        let reason = decision.reason.filter(|reason| !reason.trim().is_empty());
+        let reason_code = decision.reason_code;
+        move |conn| {
+            async move {
+                // Only move the application if nobody else changed it since
+                // we read it.
+                let updated = diesel::update(
+                    schema::applications::dsl::applications
+                        .find(id)
+                        .filter(schema::applications::dsl::status.eq(from))
+                        .filter(schema::applications::dsl::updated_at.eq(updated_at)),
+                )
+                .set((
+                    schema::applications::dsl::status.eq(status),
+                    schema::applications::dsl::updated_at.eq(now),
+                    schema::applications::dsl::completed_at.eq(completed_at),
+                ))
+                .returning(Application::as_returning())
+                .get_result(conn)
+                .await
+                .optional()?;
+
+                let Some(updated) = updated else {
+                    return anyhow::Ok(None);
+                };
+
+                diesel::insert_into(schema::application_status_events::table)
+                    .values(InsertStatusEvent {
+                        application_id: id,
+                        from_status: from,
+                        to_status: status,
+                        actor_twitch_user_id: user.twitch_user_id,
+                        actor_twitch_username: &user.twitch_username,
+                        reason: reason.as_deref(),
+                        reason_code: reason_code.as_deref(),
+                        created_at: now,
+                    })
+                    .execute(conn)
+                    .await?;
+
+                if let Some(message) = &message {
+                    diesel::insert_into(schema::application_comments::table)
+                        .values(InsertComment {
+                            application_id: id,
+                            comment: message,
+                            comment_html: &markdown::render(message),
+                            twitch_user_id: user.twitch_user_id,
+                            twitch_username: &user.twitch_username,
+                            twitch_display_name: &user.twitch_display_name,
+                            twitch_profile_image_url: &user.twitch_profile_image_url,
+                            visibility: CommentVisibility::Public,
+                            parent_id: None,
+                        })
+                        .execute(conn)
+                        .await?;
+                }
+
+                anyhow::Ok(Some(updated))
+            }
+            .scope_boxed()
+        }
+    })
+    .await
+    .map_err(|err| {
+        tracing::error!("Failed to update application: {err:#}");
+        ApiError::internal_server_error()
+    })
+}
+
+/// The error for when an application changed since the client read it,
+/// carrying the current state of the application.
+async fn changed_underneath(db: &mut AsyncPgConnection, id: i32, error: fn(&'static str) -> ApiError) -> ApiError {
+    match Application::fetch_by_id(db, id).await {
+        Ok(current) => error("the application was changed by someone else").with_data(current),
+        Err(err) => {
+            tracing::error!("Failed to fetch application: {err}");
+            ApiError::internal_server_error()
+        }
+    }
 }
 
 /// POST /applications/:id
-/// Update an application by id
-/// Scope: admin
+/// Move an application to another status. Requires an If-Match header with
+/// the ETag of the application or `expected_updated_at` in the body. A
+/// `reason_code` or `message` is rendered into the comment thread for the
+/// applicant
+/// Scope: applications:decide (and comments:write with a message)
 async fn update_application(
     State(global): State<Arc<Global>>,
     Path(id): Path<i32>,
-    TwitchAdminUser(user): TwitchAdminUser,
-    body: Json<UpdateApplicationRequest>,
-) -> Result<Json<UpdateApplicationResponse>, ApiError> {
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsDecide>,
+    headers: HeaderMap,
+    Json(body): Json<UpdateApplicationRequest>,
+) -> Result<WithEtag<UpdateApplicationResponse>, ApiError> {
+    if body.status == ApplicationStatus::Withdrawn {
+        return Err(ApiError::bad_request("only the applicant can withdraw an application"));
+    }
+
     let mut db = global.database.get().await.map_err(|err| {
         tracing::error!("Failed to get database: {err}");
         ApiError::internal_server_error()
     })?;
 
+    let message = templates::resolve_message(&mut db, body.reason_code.as_deref(), body.message).await?;
+    if message.is_some() && !has_permission(&global, &user, Permission::CommentsWrite).await? {
+        return Err(ApiError::forbidden("you do not have the scope comments:write"));
+    }
+
     let application = Application::fetch_by_id(&mut db, id)
         .await
         .map_err(|err| {
@@ -95,57 +314,148 @@ async fn update_application(
         })?
         .ok_or_else(ApiError::not_found)?;
 
-    diesel::update(schema::applications::dsl::applications.find(id))
-        .set((
-            schema::applications::dsl::status.eq(body.status),
-            schema::applications::dsl::updated_at.eq(chrono::Utc::now()),
-        ))
-        .execute(&mut db)
-        .await
-        .map_err(|err| {
-            tracing::error!("Failed to update application: {err}");
-            ApiError::internal_server_error()
-        })?;
+    match (headers.get(header::IF_MATCH), body.expected_updated_at) {
+        (Some(header), _) if !if_match(header, &application) => {
+            return Err(ApiError::precondition_failed("the application was changed by someone else").with_data(application));
+        }
+        (None, Some(expected_updated_at)) if expected_updated_at != application.updated_at => {
+            return Err(ApiError::conflict("the application was changed by someone else").with_data(application));
+        }
+        (None, None) => {
+            return Err(ApiError::precondition_required(
+                "an If-Match header or expected_updated_at is required",
+            ));
+        }
+        _ => {}
+    }
 
-    let comment = match body.status {
-        ApplicationStatus::Approved => "Moved to accepted",
-        ApplicationStatus::Rejected => "Moved to rejected",
-        ApplicationStatus::Maybe => "Moved to maybe",
-        ApplicationStatus::Pending => "Moved to pending",
+    let decision = Decision {
+        reason: body.reason,
+        reason_code: body.reason_code,
+        message,
     };
 
-    diesel::insert_into(schema::application_comments::dsl::application_comments)
-        .values(InsertComment {
-            application_id: id,
-            comment,
-            twitch_user_id: user.twitch_user_id,
-            twitch_username: &user.twitch_username,
-            twitch_display_name: &user.twitch_display_name,
-            twitch_profile_image_url: &user.twitch_profile_image_url,
-        })
-        .execute(&mut db)
+    let Some(application) = transition(&mut db, &application, body.status, &user, decision).await? else {
+        let error = if headers.contains_key(header::IF_MATCH) {
+            ApiError::precondition_failed
+        } else {
+            ApiError::conflict
+        };
+
+        return Err(changed_underneath(&mut db, id, error).await);
+    };
+
+    Ok((
+        [(header::ETAG, etag(&application))],
+        Json(UpdateApplicationResponse {
+            application_id: application.id,
+        }),
+    ))
+}
+
+/// POST /applications/:id/withdraw
+/// Withdraw your own application
+/// Scope: user (own application)
+async fn withdraw_application(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    TwitchUser(user): TwitchUser,
+    body: Option<Json<WithdrawApplicationRequest>>,
+) -> Result<Json<UpdateApplicationResponse>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let application = Application::fetch_by_id(&mut db, id)
         .await
         .map_err(|err| {
-            tracing::error!("Failed to add comment: {err}");
+            tracing::error!("Failed to fetch application: {err}");
             ApiError::internal_server_error()
-        })?;
+        })?
+        .filter(|application| application.twitch_id == user.twitch_user_id)
+        .ok_or_else(ApiError::not_found)?;
+
+    let decision = Decision {
+        reason: body.and_then(|Json(body)| body.reason),
+        ..Default::default()
+    };
 
-    Ok(Json(UpdateApplicationResponse { application_id: application.id }))
+    if transition(&mut db, &application, ApplicationStatus::Withdrawn, &user, decision)
+        .await?
+        .is_none()
+    {
+        return Err(changed_underneath(&mut db, id, ApiError::conflict).await);
+    }
+
+    Ok(Json(UpdateApplicationResponse {
+        application_id: application.id,
+    }))
 }
 
 #[derive(serde::Deserialize)]
 struct AddCommentRequest {
     comment: String,
+    /// Internal comments are hidden from the applicant.
+    #[serde(default)]
+    visibility: CommentVisibility,
+    /// The top level comment to reply to.
+    parent_id: Option<i32>,
 }
 
 #[derive(serde::Serialize)]
 struct AddCommentResponse {
     comment_id: i32,
+    /// The staff that were @mentioned.
+    mentions: Vec<String>,
+}
+
+/// Make sure a reply goes to a top level comment of the same application that
+/// the user can see.
+async fn validate_parent(
+    db: &mut AsyncPgConnection,
+    application_id: i32,
+    parent_id: i32,
+    is_applicant: bool,
+    visibility: CommentVisibility,
+) -> Result<(), ApiError> {
+    let parent = schema::application_comments::dsl::application_comments
+        .find(parent_id)
+        .filter(schema::application_comments::dsl::application_id.eq(application_id))
+        .select(ApplicationComment::as_select())
+        .get_result(db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch comment: {err}");
+            ApiError::internal_server_error()
+        })?
+        .filter(|parent| !is_applicant || parent.visibility == CommentVisibility::Public)
+        .ok_or_else(|| ApiError::bad_request("parent comment not found"))?;
+
+    if parent.parent_id.is_some() {
+        return Err(ApiError::bad_request("replies can only be made to top level comments"));
+    }
+
+    if parent.deleted_at.is_some() {
+        return Err(ApiError::bad_request("can not reply to a deleted comment"));
+    }
+
+    // A public reply would show up for the applicant without what it replies
+    // to.
+    if parent.visibility == CommentVisibility::Internal && visibility == CommentVisibility::Public {
+        return Err(ApiError::bad_request("replies to internal comments must be internal"));
+    }
+
+    Ok(())
 }
 
 /// POST /applications/:id/comment
-/// Add a comment to an application
-/// Scope: user (own application) or admin (any application)
+/// Add a comment or a reply to a comment to an application, `visibility` picks
+/// whether the applicant can see it. Comments are written in a subset of
+/// Markdown and staff can @mention other staff
+/// Scope: user (own application) or comments:write (any application), and
+/// comments:write_internal for internal comments
 async fn add_comment(
     State(global): State<Arc<Global>>,
     Path(id): Path<i32>,
@@ -165,9 +475,8 @@ async fn add_comment(
         })?
         .ok_or_else(ApiError::not_found)?;
 
-    if application.twitch_id != twitch_user_id.twitch_user_id
-        && !global.config.admin_twitch_ids.contains(&twitch_user_id.twitch_user_id)
-    {
+    let is_applicant = application.twitch_id == twitch_user_id.twitch_user_id;
+    if !is_applicant && !has_permission(&global, &twitch_user_id, Permission::CommentsWrite).await? {
         return Err(ApiError::not_found());
     }
 
@@ -175,34 +484,89 @@ async fn add_comment(
         return Err(ApiError::bad_request("comment too long"));
     }
 
-    let comment_id = diesel::insert_into(schema::application_comments::dsl::application_comments)
-        .values(InsertComment {
-            application_id: id,
-            comment: &body.comment,
-            twitch_user_id: twitch_user_id.twitch_user_id,
-            twitch_username: &twitch_user_id.twitch_username,
-            twitch_display_name: &twitch_user_id.twitch_display_name,
-            twitch_profile_image_url: &twitch_user_id.twitch_profile_image_url,
+    if body.visibility == CommentVisibility::Internal
+        && !has_permission(&global, &twitch_user_id, Permission::CommentsWriteInternal).await?
+    {
+        return Err(ApiError::forbidden("you do not have the scope comments:write_internal"));
+    }
+
+    if let Some(parent_id) = body.parent_id {
+        validate_parent(&mut db, id, parent_id, is_applicant, body.visibility).await?;
+    }
+
+    let (comment, mentions) = db
+        .transaction({
+            let (user, body) = (twitch_user_id.clone(), body.0);
+            move |conn| {
+                async move {
+                    let comment = diesel::insert_into(schema::application_comments::dsl::application_comments)
+                        .values(InsertComment {
+                            application_id: id,
+                            comment: &body.comment,
+                            comment_html: &markdown::render(&body.comment),
+                            twitch_user_id: user.twitch_user_id,
+                            twitch_username: &user.twitch_username,
+                            twitch_display_name: &user.twitch_display_name,
+                            twitch_profile_image_url: &user.twitch_profile_image_url,
+                            visibility: body.visibility,
+                            parent_id: body.parent_id,
+                        })
+                        .returning(ApplicationComment::as_returning())
+                        .get_result(conn)
+                        .await?;
+
+                    let mentions = mentions::record(conn, &comment).await?;
+
+                    anyhow::Ok((comment, mentions))
+                }
+                .scope_boxed()
+            }
         })
-        .returning(schema::application_comments::dsl::id)
-        .get_result(&mut db)
         .await
         .map_err(|err| {
-            tracing::error!("Failed to add comment: {err}");
+            tracing::error!("Failed to add comment: {err:#}");
             ApiError::internal_server_error()
         })?;
 
-    Ok(Json(AddCommentResponse { comment_id }))
+    Ok(Json(AddCommentResponse {
+        comment_id: comment.id,
+        mentions,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+struct GetCommentsRequest {
+    /// Only get comments posted after this, for polling for new comments.
+    since: Option<DateTime<Utc>>,
+}
+
+/// The position of the last comment of a page.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CommentCursor {
+    created_at: DateTime<Utc>,
+    id: i32,
+}
+
+#[derive(serde::Serialize)]
+struct GetCommentsResponse {
+    comments: Vec<ApplicationComment>,
+    next_cursor: Option<String>,
 }
 
 /// GET /applications/:id/comments
-/// Get comments for an application
-/// Scope: user (own application) or admin (any application)
+/// Get a page of the comments of an application, oldest first. The applicant
+/// only gets the public ones
+/// Scope: user (own application) or comments:read (any application)
 async fn get_comments(
     State(global): State<Arc<Global>>,
     Path(id): Path<i32>,
     TwitchUser(twitch_user_id): TwitchUser,
-) -> Result<Json<Vec<ApplicationComment>>, ApiError> {
+    Query(request): Query<GetCommentsRequest>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<GetCommentsResponse>, ApiError> {
+    let limit = page.limit()?;
+    let cursor = page.cursor().map(decode_cursor::<CommentCursor>).transpose()?;
+
     let mut db = global.database.get().await.map_err(|err| {
         tracing::error!("Failed to get database: {err}");
         ApiError::internal_server_error()
@@ -216,14 +580,46 @@ async fn get_comments(
         })?
         .ok_or_else(ApiError::not_found)?;
 
-    if application.twitch_id != twitch_user_id.twitch_user_id
-        && !global.config.admin_twitch_ids.contains(&twitch_user_id.twitch_user_id)
-    {
+    let is_applicant = application.twitch_id == twitch_user_id.twitch_user_id;
+    if !is_applicant && !has_permission(&global, &twitch_user_id, Permission::CommentsRead).await? {
         return Err(ApiError::not_found());
     }
 
-    let comments = schema::application_comments::dsl::application_comments
-        .filter(schema::application_comments::dsl::application_id.eq(id))
+    // Staff applying themselves still do not get to read what was said about
+    // them internally.
+    let visible: &[CommentVisibility] = if is_applicant {
+        &[CommentVisibility::Public]
+    } else {
+        &[CommentVisibility::Public, CommentVisibility::Internal]
+    };
+
+    let mut query = diesel::QueryDsl::into_boxed(
+        schema::application_comments::dsl::application_comments
+            .filter(schema::application_comments::dsl::application_id.eq(id))
+            .filter(schema::application_comments::dsl::visibility.eq_any(visible)),
+    );
+
+    if let Some(since) = request.since {
+        query = query.filter(schema::application_comments::dsl::created_at.gt(since));
+    }
+
+    if let Some(cursor) = cursor {
+        query = query.filter(
+            schema::application_comments::dsl::created_at.gt(cursor.created_at).or(
+                schema::application_comments::dsl::created_at
+                    .eq(cursor.created_at)
+                    .and(schema::application_comments::dsl::id.gt(cursor.id)),
+            ),
+        );
+    }
+
+    // Fetch one more than we return so we know if there is another page.
+    let mut comments = query
+        .order((
+            schema::application_comments::dsl::created_at.asc(),
+            schema::application_comments::dsl::id.asc(),
+        ))
+        .limit(limit + 1)
         .select(ApplicationComment::as_select())
         .load(&mut db)
         .await
@@ -232,5 +628,355 @@ async fn get_comments(
             ApiError::internal_server_error()
         })?;
 
-    Ok(Json(comments))
+    let next_cursor = if comments.len() as i64 > limit {
+        comments.truncate(limit as usize);
+        comments.last().map(|comment| {
+            encode_cursor(&CommentCursor {
+                created_at: comment.created_at,
+                id: comment.id,
+            })
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(GetCommentsResponse { comments, next_cursor }))
+}
+
+#[derive(Insertable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::comment_revisions)]
+struct InsertCommentRevision<'a> {
+    comment_id: i32,
+    comment: &'a str,
+    twitch_user_id: i32,
+    twitch_username: &'a str,
+}
+
+/// Fetch a comment of an application that the user can see and change: their
+/// own comments, or any comment for admins.
+async fn fetch_editable_comment(
+    global: &Arc<Global>,
+    db: &mut AsyncPgConnection,
+    id: i32,
+    comment_id: i32,
+    user: &User,
+) -> Result<ApplicationComment, ApiError> {
+    let application = Application::fetch_by_id(db, id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch application: {err}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(ApiError::not_found)?;
+
+    let is_applicant = application.twitch_id == user.twitch_user_id;
+    if !is_applicant && !has_permission(global, user, Permission::CommentsRead).await? {
+        return Err(ApiError::not_found());
+    }
+
+    let comment = schema::application_comments::dsl::application_comments
+        .find(comment_id)
+        .filter(schema::application_comments::dsl::application_id.eq(id))
+        .select(ApplicationComment::as_select())
+        .get_result(db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch comment: {err}");
+            ApiError::internal_server_error()
+        })?
+        .filter(|comment| !is_applicant || comment.visibility == CommentVisibility::Public)
+        .ok_or_else(ApiError::not_found)?;
+
+    if comment.twitch_user_id != user.twitch_user_id && !has_permission(global, user, Permission::CommentsModerate).await? {
+        return Err(ApiError::forbidden("you can only change your own comments"));
+    }
+
+    if comment.deleted_at.is_some() {
+        return Err(ApiError::conflict("the comment was deleted"));
+    }
+
+    Ok(comment)
+}
+
+/// Replace the text of a comment, keeping the text it had as a revision.
+/// Deleting a comment clears its text and marks it as deleted.
+async fn revise_comment(
+    db: &mut AsyncPgConnection,
+    current: &ApplicationComment,
+    user: &User,
+    text: Option<String>,
+) -> Result<ApplicationComment, ApiError> {
+    db.transaction({
+        let (id, previous, user) = (current.id, current.comment.clone(), user.clone());
+        move |conn| {
+            async move {
+                // Only change the comment if nobody else changed it since we
+                // read it, so the revision holds the text that was replaced.
+                let update = diesel::update(
+                    schema::application_comments::dsl::application_comments
+                        .find(id)
+                        .filter(schema::application_comments::dsl::comment.eq(&previous))
+                        .filter(schema::application_comments::dsl::deleted_at.is_null()),
+                );
+
+                let now = chrono::Utc::now();
+                let comment = match text {
+                    Some(text) => {
+                        update
+                            .set((
+                                schema::application_comments::dsl::comment_html.eq(markdown::render(&text)),
+                                schema::application_comments::dsl::comment.eq(text),
+                                schema::application_comments::dsl::edited_at.eq(now),
+                            ))
+                            .returning(ApplicationComment::as_returning())
+                            .get_result(conn)
+                            .await
+                    }
+                    None => {
+                        update
+                            .set((
+                                schema::application_comments::dsl::comment.eq(""),
+                                schema::application_comments::dsl::comment_html.eq(""),
+                                schema::application_comments::dsl::deleted_at.eq(now),
+                                schema::application_comments::dsl::deleted_by.eq(user.twitch_user_id),
+                            ))
+                            .returning(ApplicationComment::as_returning())
+                            .get_result(conn)
+                            .await
+                    }
+                }
+                .optional()?;
+
+                let Some(comment) = comment else {
+                    return anyhow::Ok(None);
+                };
+
+                if comment.deleted_at.is_none() {
+                    mentions::record(conn, &comment).await?;
+                }
+
+                diesel::insert_into(schema::comment_revisions::table)
+                    .values(InsertCommentRevision {
+                        comment_id: id,
+                        comment: &previous,
+                        twitch_user_id: user.twitch_user_id,
+                        twitch_username: &user.twitch_username,
+                    })
+                    .execute(conn)
+                    .await?;
+
+                anyhow::Ok(Some(comment))
+            }
+            .scope_boxed()
+        }
+    })
+    .await
+    .map_err(|err| {
+        tracing::error!("Failed to revise comment: {err:#}");
+        ApiError::internal_server_error()
+    })?
+    .ok_or_else(|| ApiError::conflict("the comment was changed by someone else"))
+}
+
+#[derive(serde::Deserialize)]
+struct EditCommentRequest {
+    comment: String,
+}
+
+/// PATCH /applications/:id/comments/:comment_id
+/// Edit a comment, the previous text is kept as a revision
+/// Scope: user (own comment) or comments:moderate (any comment)
+async fn edit_comment(
+    State(global): State<Arc<Global>>,
+    Path((id, comment_id)): Path<(i32, i32)>,
+    TwitchUser(user): TwitchUser,
+    Json(body): Json<EditCommentRequest>,
+) -> Result<Json<ApplicationComment>, ApiError> {
+    if body.comment.trim().is_empty() {
+        return Err(ApiError::bad_request("comment must not be empty"));
+    }
+
+    if body.comment.len() > 1000 {
+        return Err(ApiError::bad_request("comment too long"));
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let comment = fetch_editable_comment(&global, &mut db, id, comment_id, &user).await?;
+
+    revise_comment(&mut db, &comment, &user, Some(body.comment)).await.map(Json)
+}
+
+/// DELETE /applications/:id/comments/:comment_id
+/// Delete a comment, leaving a tombstone in the thread. The text is kept as a
+/// revision
+/// Scope: user (own comment) or comments:moderate (any comment)
+async fn delete_comment(
+    State(global): State<Arc<Global>>,
+    Path((id, comment_id)): Path<(i32, i32)>,
+    TwitchUser(user): TwitchUser,
+) -> Result<Json<ApplicationComment>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let comment = fetch_editable_comment(&global, &mut db, id, comment_id, &user).await?;
+
+    revise_comment(&mut db, &comment, &user, None).await.map(Json)
+}
+
+/// GET /applications/:id/comments/:comment_id/revisions
+/// Get the earlier texts of a comment, oldest first
+/// Scope: comments:read
+async fn get_comment_revisions(
+    State(global): State<Arc<Global>>,
+    Path((id, comment_id)): Path<(i32, i32)>,
+    _: RequirePermission<scopes::CommentsRead>,
+) -> Result<Json<Vec<CommentRevision>>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let revisions = schema::comment_revisions::table
+        .filter(
+            schema::comment_revisions::dsl::comment_id.eq_any(
+                schema::application_comments::dsl::application_comments
+                    .find(comment_id)
+                    .filter(schema::application_comments::dsl::application_id.eq(id))
+                    .select(schema::application_comments::dsl::id),
+            ),
+        )
+        .order((schema::comment_revisions::dsl::created_at, schema::comment_revisions::dsl::id))
+        .select(CommentRevision::as_select())
+        .load(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch comment revisions: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(revisions))
+}
+
+#[derive(serde::Serialize)]
+struct TimeInStatus {
+    status: ApplicationStatus,
+    seconds: i64,
+}
+
+/// A status change as the applicant sees it, without who made it or why.
+#[derive(serde::Serialize)]
+struct PublicStatusEvent {
+    id: i32,
+    application_id: i32,
+    from_status: ApplicationStatus,
+    to_status: ApplicationStatus,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<ApplicationStatusEvent> for PublicStatusEvent {
+    fn from(event: ApplicationStatusEvent) -> Self {
+        PublicStatusEvent {
+            id: event.id,
+            application_id: event.application_id,
+            from_status: event.from_status,
+            to_status: event.to_status,
+            created_at: event.created_at,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum StatusEvents {
+    Staff(Vec<ApplicationStatusEvent>),
+    Applicant(Vec<PublicStatusEvent>),
+}
+
+#[derive(serde::Serialize)]
+struct GetHistoryResponse {
+    events: StatusEvents,
+    /// Total time spent in each status, the current one counted up to now.
+    time_in_status: Vec<TimeInStatus>,
+}
+
+/// Add up how long an application spent in each status. Every application
+/// starts out pending when it is submitted.
+fn time_in_status(application: &Application, events: &[ApplicationStatusEvent]) -> Vec<TimeInStatus> {
+    let mut totals: Vec<TimeInStatus> = Vec::new();
+    let mut add = |status, seconds| match totals.iter_mut().find(|total| total.status == status) {
+        Some(total) => total.seconds += seconds,
+        None => totals.push(TimeInStatus { status, seconds }),
+    };
+
+    let mut since = application.created_at;
+    for event in events {
+        add(event.from_status, (event.created_at - since).num_seconds());
+        since = event.created_at;
+    }
+
+    add(application.status, (chrono::Utc::now() - since).num_seconds());
+
+    totals
+}
+
+/// GET /applications/:id/history
+/// Get every status change of an application. The applicant only sees the
+/// statuses, not who changed them or why
+/// Scope: user (own application) or applications:read (any application)
+async fn get_history(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    TwitchUser(user): TwitchUser,
+) -> Result<Json<GetHistoryResponse>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let application = Application::fetch_by_id(&mut db, id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch application: {err}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(ApiError::not_found)?;
+
+    let is_applicant = application.twitch_id == user.twitch_user_id;
+    if !is_applicant && !has_permission(&global, &user, Permission::ApplicationsRead).await? {
+        return Err(ApiError::not_found());
+    }
+
+    let events = schema::application_status_events::table
+        .filter(schema::application_status_events::dsl::application_id.eq(id))
+        .order((
+            schema::application_status_events::dsl::created_at,
+            schema::application_status_events::dsl::id,
+        ))
+        .select(ApplicationStatusEvent::as_select())
+        .load(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch status events: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    let time_in_status = time_in_status(&application, &events);
+
+    // Staff looking at their own application get the same view as any other
+    // applicant.
+    let events = if is_applicant {
+        StatusEvents::Applicant(events.into_iter().map(PublicStatusEvent::from).collect())
+    } else {
+        StatusEvents::Staff(events)
+    };
+
+    Ok(Json(GetHistoryResponse { events, time_in_status }))
 }
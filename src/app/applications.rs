@@ -1,66 +1,212 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use axum::extract::{Query, State};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use diesel::dsl::{exists, not};
+use diesel::pg::Pg;
 use diesel::prelude::Insertable;
-use diesel::{ExpressionMethods, PgTextExpressionMethods, QueryDsl, SelectableHelper};
-use diesel_async::RunQueryDsl;
+use diesel::sql_types::{BigInt, Float, Nullable};
+use diesel::{BoolExpressionMethods, ExpressionMethods, PgTextExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use serde::de::value::StrDeserializer;
 
-use super::auth::{TwitchAdminUser, TwitchUser};
+use super::application::{transition, Decision, InsertComment};
+use super::auth::{has_permission, RequirePermission, TwitchUser};
 use super::error::ApiError;
-use crate::database::enums::{ApplicationStatus, TwitchAccountType};
+use super::permissions::{scopes, Permission};
+use super::{markdown, mentions, search, templates};
+use crate::database::enums::{ApplicationStatus, CommentVisibility, TwitchAccountType};
 use crate::database::schema;
-use crate::database::types::Application;
+use crate::database::types::{Application, ApplicationComment};
 use crate::global::Global;
 
 pub fn routes() -> Router<Arc<Global>> {
     Router::new()
         .route("/", get(get_applications))
+        .nest("/views", super::views::routes())
+        .nest("/export", super::export::routes())
+        .nest("/metrics", super::metrics::routes())
+        .nest("/mentions", super::mentions::routes())
         .route("/me", get(get_my_applications))
         .route("/submit", post(submit_application))
+        .route("/bulk", post(bulk_update_applications))
 }
 
-#[derive(serde::Deserialize)]
-struct GetApplicationsRequest {
-    status: Option<ApplicationStatus>,
-    twitch_account_type: Option<TwitchAccountType>,
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct GetApplicationsRequest {
+    /// Comma separated, e.g. `pending,maybe`.
+    status: Option<String>,
+    /// Comma separated, e.g. `affiliate,partner`.
+    twitch_account_type: Option<String>,
     min_follow_count: Option<i32>,
+    max_follow_count: Option<i32>,
+    /// RFC 3339 timestamps, the ranges are inclusive.
+    created_after: Option<String>,
+    created_before: Option<String>,
+    updated_after: Option<String>,
+    updated_before: Option<String>,
+    /// Whether the requesting user has commented on the application.
+    commented_by_me: Option<bool>,
+    /// Whether anyone other than the applicant has commented on the
+    /// application.
+    has_reviewer_comment: Option<bool>,
+    completed: Option<bool>,
     twitch_username: Option<String>,
+    q: Option<String>,
 }
 
-/// GET /applications
-/// Get all applications by some query filters
-/// Scope: admin
-async fn get_applications(
-    State(global): State<Arc<Global>>,
-    TwitchAdminUser(_): TwitchAdminUser,
-    Query(request): Query<GetApplicationsRequest>,
-) -> Result<Json<Vec<Application>>, ApiError> {
-    let mut db = global.database.get().await.map_err(|err| {
-        tracing::error!("Failed to get database: {err}");
-        ApiError::internal_server_error()
-    })?;
+impl GetApplicationsRequest {
+    /// The full-text search query, if the request has a non-blank one.
+    fn search_query(&self) -> Option<&str> {
+        self.q.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+}
+
+/// Parse a comma separated list of enum values.
+fn parse_list<T: serde::de::DeserializeOwned>(name: &str, value: &str) -> Result<Vec<T>, ApiError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .map(|item| {
+            T::deserialize(StrDeserializer::<serde::de::value::Error>::new(item))
+                .map_err(|_| ApiError::bad_request(format!("invalid {name}: {item:?}")))
+        })
+        .collect()
+}
+
+fn parse_timestamp(name: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, ApiError> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|timestamp| timestamp.to_utc())
+                .map_err(|_| ApiError::bad_request(format!("{name} must be an RFC 3339 timestamp")))
+        })
+        .transpose()
+}
 
+/// Make sure the start of an inclusive range is not after its end.
+fn check_range<T: PartialOrd>(
+    (start_name, start): (&str, Option<T>),
+    (end_name, end): (&str, Option<T>),
+) -> Result<(), ApiError> {
+    match (start, end) {
+        (Some(start), Some(end)) if start > end => {
+            Err(ApiError::bad_request(format!("{start_name} must not exceed {end_name}")))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Build the query for the applications matching the filters of a request.
+/// `twitch_user_id` is the user making the request.
+pub fn filter_applications(
+    request: &GetApplicationsRequest,
+    twitch_user_id: i32,
+) -> Result<schema::applications::BoxedQuery<'static, Pg>, ApiError> {
     let mut query = schema::applications::table.into_boxed();
 
-    if let Some(status) = request.status {
-        query = query.filter(schema::applications::dsl::status.eq(status));
+    if let Some(status) = &request.status {
+        let status = parse_list::<ApplicationStatus>("status", status)?;
+        query = query.filter(schema::applications::dsl::status.eq_any(status));
     }
 
-    if let Some(twitch_account_type) = request.twitch_account_type {
-        query = query.filter(schema::applications::dsl::twitch_account_type.eq(twitch_account_type));
+    if let Some(twitch_account_type) = &request.twitch_account_type {
+        let twitch_account_type = parse_list::<TwitchAccountType>("twitch_account_type", twitch_account_type)?;
+        query = query.filter(schema::applications::dsl::twitch_account_type.eq_any(twitch_account_type));
     }
 
-    if let Some(min_follow_count) = request.min_follow_count {
-        if min_follow_count < 0 {
-            return Err(ApiError::bad_request("min_follow_count must be greater than 0"));
+    for (name, follow_count) in [
+        ("min_follow_count", request.min_follow_count),
+        ("max_follow_count", request.max_follow_count),
+    ] {
+        if follow_count.is_some_and(|follow_count| follow_count < 0) {
+            return Err(ApiError::bad_request(format!("{name} must not be negative")));
         }
+    }
 
+    check_range(
+        ("min_follow_count", request.min_follow_count),
+        ("max_follow_count", request.max_follow_count),
+    )?;
+
+    if let Some(min_follow_count) = request.min_follow_count {
         query = query.filter(schema::applications::dsl::follow_count.ge(min_follow_count));
     }
 
-    if let Some(twitch_username) = request.twitch_username {
+    if let Some(max_follow_count) = request.max_follow_count {
+        query = query.filter(schema::applications::dsl::follow_count.le(max_follow_count));
+    }
+
+    let created_after = parse_timestamp("created_after", request.created_after.as_deref())?;
+    let created_before = parse_timestamp("created_before", request.created_before.as_deref())?;
+    check_range(("created_after", created_after), ("created_before", created_before))?;
+
+    if let Some(created_after) = created_after {
+        query = query.filter(schema::applications::dsl::created_at.ge(created_after));
+    }
+
+    if let Some(created_before) = created_before {
+        query = query.filter(schema::applications::dsl::created_at.le(created_before));
+    }
+
+    let updated_after = parse_timestamp("updated_after", request.updated_after.as_deref())?;
+    let updated_before = parse_timestamp("updated_before", request.updated_before.as_deref())?;
+    check_range(("updated_after", updated_after), ("updated_before", updated_before))?;
+
+    if let Some(updated_after) = updated_after {
+        query = query.filter(schema::applications::dsl::updated_at.ge(updated_after));
+    }
+
+    if let Some(updated_before) = updated_before {
+        query = query.filter(schema::applications::dsl::updated_at.le(updated_before));
+    }
+
+    if let Some(commented_by_me) = request.commented_by_me {
+        let comments = exists(
+            schema::application_comments::table
+                .filter(schema::application_comments::dsl::application_id.eq(schema::applications::dsl::id))
+                .filter(schema::application_comments::dsl::twitch_user_id.eq(twitch_user_id))
+                .filter(schema::application_comments::dsl::deleted_at.is_null()),
+        );
+
+        query = if commented_by_me {
+            query.filter(comments)
+        } else {
+            query.filter(not(comments))
+        };
+    }
+
+    if let Some(has_reviewer_comment) = request.has_reviewer_comment {
+        let comments = exists(
+            schema::application_comments::table
+                .filter(schema::application_comments::dsl::application_id.eq(schema::applications::dsl::id))
+                .filter(schema::application_comments::dsl::twitch_user_id.ne(schema::applications::dsl::twitch_id))
+                .filter(schema::application_comments::dsl::deleted_at.is_null()),
+        );
+
+        query = if has_reviewer_comment {
+            query.filter(comments)
+        } else {
+            query.filter(not(comments))
+        };
+    }
+
+    if let Some(completed) = request.completed {
+        query = if completed {
+            query.filter(schema::applications::dsl::completed_at.is_not_null())
+        } else {
+            query.filter(schema::applications::dsl::completed_at.is_null())
+        };
+    }
+
+    if let Some(twitch_username) = &request.twitch_username {
         if twitch_username.len() > 100 {
             return Err(ApiError::bad_request("twitch_username too long"));
         }
@@ -68,16 +214,380 @@ async fn get_applications(
         query = query.filter(schema::applications::dsl::twitch_username.ilike(format!("%{}%", twitch_username)));
     }
 
-    let applications = query
-        .select(Application::as_select())
-        .load::<Application>(&mut db)
+    if let Some(q) = request.search_query() {
+        if q.len() > search::MAX_QUERY_LENGTH {
+            return Err(ApiError::bad_request("q too long"));
+        }
+
+        query = query.filter(search::matches(q));
+    }
+
+    Ok(query)
+}
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+/// How many comments an application has, not counting deleted ones. Internal
+/// comments are only counted for staff.
+pub fn comment_count(include_internal: bool) -> search::ApplicationsExpression<Nullable<BigInt>> {
+    let visible = if include_internal {
+        vec![CommentVisibility::Public, CommentVisibility::Internal]
+    } else {
+        vec![CommentVisibility::Public]
+    };
+
+    Box::new(
+        schema::application_comments::table
+            .filter(schema::application_comments::dsl::application_id.eq(schema::applications::dsl::id))
+            .filter(schema::application_comments::dsl::deleted_at.is_null())
+            .filter(schema::application_comments::dsl::visibility.eq_any(visible))
+            .count()
+            .single_value(),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    CreatedAt,
+    UpdatedAt,
+    FollowCount,
+    Status,
+    /// Only available when searching, always most relevant first.
+    Relevance,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ApplicationsSort {
+    /// Defaults to relevance when searching and created_at otherwise.
+    sort: Option<SortKey>,
+    #[serde(default)]
+    order: SortOrder,
+}
+
+impl ApplicationsSort {
+    /// The key to sort the applications matching a request by.
+    pub fn resolve(&self, request: &GetApplicationsRequest) -> Result<SortKey, ApiError> {
+        let searching = request.search_query().is_some();
+
+        match self.sort {
+            Some(SortKey::Relevance) if !searching => Err(ApiError::bad_request("sorting by relevance requires q")),
+            Some(SortKey::Relevance) if self.order != SortOrder::Desc => {
+                Err(ApiError::bad_request("relevance can only be sorted in descending order"))
+            }
+            Some(sort) => Ok(sort),
+            None if searching => Ok(SortKey::Relevance),
+            None => Ok(SortKey::CreatedAt),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PageRequest {
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+impl PageRequest {
+    /// How many items to return at most.
+    pub fn limit(&self) -> Result<i64, ApiError> {
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(ApiError::bad_request(format!("limit must be between 1 and {MAX_PAGE_LIMIT}")));
+        }
+
+        Ok(limit)
+    }
+
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+}
+
+/// Turn the position of the last item of a page into the opaque string that
+/// clients pass back to get the next page.
+pub fn encode_cursor(cursor: &impl serde::Serialize) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+/// Read back a cursor made by [`encode_cursor`].
+pub fn decode_cursor<T: serde::de::DeserializeOwned>(cursor: &str) -> Result<T, ApiError> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| ApiError::bad_request("invalid cursor"))
+}
+
+/// The position of the last application of a page.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Cursor {
+    sort: SortKey,
+    order: SortOrder,
+    id: i32,
+    value: CursorValue,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum CursorValue {
+    Timestamp(DateTime<Utc>),
+    Int(i32),
+    Status(ApplicationStatus),
+    Rank(f32),
+}
+
+impl Cursor {
+    fn after(application: &Application, rank: f32, sort: SortKey, order: SortOrder) -> Self {
+        let value = match sort {
+            SortKey::CreatedAt => CursorValue::Timestamp(application.created_at),
+            SortKey::UpdatedAt => CursorValue::Timestamp(application.updated_at),
+            SortKey::FollowCount => CursorValue::Int(application.follow_count),
+            SortKey::Status => CursorValue::Status(application.status),
+            SortKey::Relevance => CursorValue::Rank(rank),
+        };
+
+        Cursor {
+            sort,
+            order,
+            id: application.id,
+            value,
+        }
+    }
+
+    fn decode(cursor: &str, sort: SortKey, order: SortOrder) -> Result<Self, ApiError> {
+        let cursor: Cursor = decode_cursor(cursor)?;
+
+        if cursor.sort != sort || cursor.order != order {
+            return Err(ApiError::bad_request("cursor does not match the requested sort"));
+        }
+
+        Ok(cursor)
+    }
+
+    fn timestamp(&self) -> Result<(DateTime<Utc>, i32), ApiError> {
+        match self.value {
+            CursorValue::Timestamp(value) => Ok((value, self.id)),
+            _ => Err(ApiError::bad_request("invalid cursor")),
+        }
+    }
+
+    fn int(&self) -> Result<(i32, i32), ApiError> {
+        match self.value {
+            CursorValue::Int(value) => Ok((value, self.id)),
+            _ => Err(ApiError::bad_request("invalid cursor")),
+        }
+    }
+
+    fn status(&self) -> Result<(ApplicationStatus, i32), ApiError> {
+        match self.value {
+            CursorValue::Status(value) => Ok((value, self.id)),
+            _ => Err(ApiError::bad_request("invalid cursor")),
+        }
+    }
+
+    fn rank(&self) -> Result<(f32, i32), ApiError> {
+        match self.value {
+            CursorValue::Rank(value) => Ok((value, self.id)),
+            _ => Err(ApiError::bad_request("invalid cursor")),
+        }
+    }
+}
+
+/// Order the query by a column, using the id to break ties, and skip every
+/// row up to and including the cursor position.
+macro_rules! keyset {
+    ($query:ident, $column:expr, $order:expr, $after:expr) => {{
+        let id = schema::applications::dsl::id;
+        match $order {
+            SortOrder::Asc => {
+                $query = $query.order_by(($column.asc(), id.asc()));
+                if let Some((value, last_id)) = $after {
+                    $query = $query.filter($column.gt(value).or($column.eq(value).and(id.gt(last_id))));
+                }
+            }
+            SortOrder::Desc => {
+                $query = $query.order_by(($column.desc(), id.desc()));
+                if let Some((value, last_id)) = $after {
+                    $query = $query.filter($column.lt(value).or($column.eq(value).and(id.lt(last_id))));
+                }
+            }
+        }
+    }};
+}
+
+#[derive(serde::Serialize)]
+pub struct ApplicationResult {
+    #[serde(flatten)]
+    pub application: Application,
+    pub comment_count: i64,
+    /// Only set when searching.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippets: Option<search::Snippets>,
+}
+
+#[derive(serde::Serialize)]
+pub struct GetApplicationsResponse {
+    applications: Vec<ApplicationResult>,
+    next_cursor: Option<String>,
+    total: i64,
+}
+
+/// GET /applications
+/// Get a page of applications by some query filters, or search them with `q`
+/// Scope: applications:read
+async fn get_applications(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsRead>,
+    Query(request): Query<GetApplicationsRequest>,
+    Query(sort): Query<ApplicationsSort>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<GetApplicationsResponse>, ApiError> {
+    list_applications(&global, user.twitch_user_id, &request, &sort, &page)
+        .await
+        .map(Json)
+}
+
+/// Build the query for the applications matching a request in the requested
+/// order, starting after the cursor if there is one.
+pub fn sort_applications(
+    request: &GetApplicationsRequest,
+    sort: &ApplicationsSort,
+    twitch_user_id: i32,
+    cursor: Option<&str>,
+) -> Result<schema::applications::BoxedQuery<'static, Pg>, ApiError> {
+    let q = request.search_query();
+    let order = sort.order;
+    let sort = sort.resolve(request)?;
+
+    let cursor = cursor.map(|cursor| Cursor::decode(cursor, sort, order)).transpose()?;
+
+    let mut query = filter_applications(request, twitch_user_id)?;
+
+    match sort {
+        SortKey::CreatedAt => {
+            let after = cursor.as_ref().map(Cursor::timestamp).transpose()?;
+            keyset!(query, schema::applications::dsl::created_at, order, after);
+        }
+        SortKey::UpdatedAt => {
+            let after = cursor.as_ref().map(Cursor::timestamp).transpose()?;
+            keyset!(query, schema::applications::dsl::updated_at, order, after);
+        }
+        SortKey::FollowCount => {
+            let after = cursor.as_ref().map(Cursor::int).transpose()?;
+            keyset!(query, schema::applications::dsl::follow_count, order, after);
+        }
+        SortKey::Status => {
+            let after = cursor.as_ref().map(Cursor::status).transpose()?;
+            keyset!(query, schema::applications::dsl::status, order, after);
+        }
+        SortKey::Relevance => {
+            // Relevance is only resolved when there is a search query.
+            if let Some(q) = q {
+                query = query.order_by((search::rank(q).desc(), schema::applications::dsl::id.desc()));
+                if let Some((rank, last_id)) = cursor.as_ref().map(Cursor::rank).transpose()? {
+                    query = query.filter(search::ranked_after(q, rank, last_id));
+                }
+            }
+        }
+    }
+
+    Ok(query)
+}
+
+/// Get a page of the applications matching a request, as seen by the user
+/// making it.
+pub async fn list_applications(
+    global: &Global,
+    twitch_user_id: i32,
+    request: &GetApplicationsRequest,
+    sort: &ApplicationsSort,
+    page: &PageRequest,
+) -> Result<GetApplicationsResponse, ApiError> {
+    let limit = page.limit()?;
+
+    let q = request.search_query();
+    let order = sort.order;
+    let query = sort_applications(request, sort, twitch_user_id, page.cursor())?;
+    let sort = sort.resolve(request)?;
+
+    // The rank is only needed for relevance cursors, so skip computing it
+    // otherwise.
+    let rank: search::ApplicationsExpression<Float> = match q {
+        Some(q) if sort == SortKey::Relevance => search::rank(q),
+        _ => Box::new(diesel::dsl::sql::<Float>("0::REAL")),
+    };
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    // Fetch one more than we return so we know if there is another page.
+    let mut applications = query
+        .limit(limit + 1)
+        .select((Application::as_select(), rank, comment_count(true)))
+        .load::<(Application, f32, Option<i64>)>(&mut db)
         .await
         .map_err(|err| {
             tracing::error!("Failed to fetch applications: {err}");
             ApiError::internal_server_error()
         })?;
 
-    Ok(Json(applications))
+    let next_cursor = if applications.len() as i64 > limit {
+        applications.truncate(limit as usize);
+        applications
+            .last()
+            .map(|(application, rank, _)| encode_cursor(&Cursor::after(application, *rank, sort, order)))
+    } else {
+        None
+    };
+
+    let mut snippets = match q {
+        Some(q) => {
+            let ids = applications
+                .iter()
+                .map(|(application, ..)| application.id)
+                .collect::<Vec<_>>();
+            search::snippets(&mut db, q, &ids).await.map_err(|err| {
+                tracing::error!("Failed to fetch search snippets: {err}");
+                ApiError::internal_server_error()
+            })?
+        }
+        None => Default::default(),
+    };
+
+    let total = filter_applications(request, twitch_user_id)?
+        .count()
+        .get_result::<i64>(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to count applications: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    let applications = applications
+        .into_iter()
+        .map(|(application, _, comment_count)| ApplicationResult {
+            snippets: snippets.remove(&application.id),
+            comment_count: comment_count.unwrap_or_default(),
+            application,
+        })
+        .collect();
+
+    Ok(GetApplicationsResponse {
+        applications,
+        next_cursor,
+        total,
+    })
 }
 
 #[derive(serde::Deserialize)]
@@ -146,7 +656,7 @@ async fn submit_application(
 async fn get_my_applications(
     State(global): State<Arc<Global>>,
     TwitchUser(user): TwitchUser,
-) -> Result<Json<Vec<Application>>, ApiError> {
+) -> Result<Json<Vec<ApplicationResult>>, ApiError> {
     let mut db = global.database.get().await.map_err(|err| {
         tracing::error!("Failed to get database: {err}");
         ApiError::internal_server_error()
@@ -154,13 +664,195 @@ async fn get_my_applications(
 
     let applications = schema::applications::table
         .filter(schema::applications::dsl::twitch_id.eq(user.twitch_user_id))
-        .select(Application::as_select())
-        .load::<Application>(&mut db)
+        .select((Application::as_select(), comment_count(false)))
+        .load::<(Application, Option<i64>)>(&mut db)
         .await
         .map_err(|err| {
             tracing::error!("Failed to fetch applications: {err}");
             ApiError::internal_server_error()
         })?;
 
+    let applications = applications
+        .into_iter()
+        .map(|(application, comment_count)| ApplicationResult {
+            application,
+            comment_count: comment_count.unwrap_or_default(),
+            snippets: None,
+        })
+        .collect();
+
     Ok(Json(applications))
 }
+
+const MAX_BULK_APPLICATIONS: usize = 100;
+
+#[derive(serde::Deserialize)]
+struct BulkUpdateRequest {
+    ids: Vec<i32>,
+    status: ApplicationStatus,
+    reason: Option<String>,
+    /// The code of a message template to send every applicant.
+    reason_code: Option<String>,
+    /// Sent to every applicant instead of the template of the reason code,
+    /// with the placeholders filled in per application.
+    message: Option<String>,
+    /// Added to the comment thread of every application that was moved.
+    comment: Option<String>,
+    /// Whether the applicants can see the comment.
+    #[serde(default)]
+    comment_visibility: CommentVisibility,
+}
+
+#[derive(serde::Serialize)]
+struct BulkUpdateResult {
+    id: i32,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Cow<'static, str>>,
+    /// The application after it was moved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    application: Option<Application>,
+}
+
+impl BulkUpdateResult {
+    fn failed(id: i32, error: impl Into<Cow<'static, str>>) -> Self {
+        BulkUpdateResult {
+            id,
+            success: false,
+            error: Some(error.into()),
+            application: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct BulkUpdateResponse {
+    results: Vec<BulkUpdateResult>,
+}
+
+/// POST /applications/bulk
+/// Move many applications to the same status in one transaction. Each
+/// application follows the same rules as POST /application/:id, and ones that
+/// can not be moved are reported without failing the others
+/// Scope: applications:decide (and comments:write with a comment or message,
+/// comments:write_internal with an internal comment)
+async fn bulk_update_applications(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsDecide>,
+    Json(body): Json<BulkUpdateRequest>,
+) -> Result<Json<BulkUpdateResponse>, ApiError> {
+    if body.ids.is_empty() || body.ids.len() > MAX_BULK_APPLICATIONS {
+        return Err(ApiError::bad_request(format!(
+            "ids must contain between 1 and {MAX_BULK_APPLICATIONS} applications"
+        )));
+    }
+
+    if body.status == ApplicationStatus::Withdrawn {
+        return Err(ApiError::bad_request("only the applicant can withdraw an application"));
+    }
+
+    if body.reason.as_ref().is_some_and(|reason| reason.len() > 1000) {
+        return Err(ApiError::bad_request("reason too long"));
+    }
+
+    let comment = body.comment.filter(|comment| !comment.trim().is_empty());
+    if comment.as_ref().is_some_and(|comment| comment.len() > 1000) {
+        return Err(ApiError::bad_request("comment too long"));
+    }
+
+    let mut ids = body.ids;
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let message = templates::resolve_message(&mut db, body.reason_code.as_deref(), body.message).await?;
+    if (comment.is_some() || message.is_some()) && !has_permission(&global, &user, Permission::CommentsWrite).await? {
+        return Err(ApiError::forbidden("you do not have the scope comments:write"));
+    }
+
+    if comment.is_some()
+        && body.comment_visibility == CommentVisibility::Internal
+        && !has_permission(&global, &user, Permission::CommentsWriteInternal).await?
+    {
+        return Err(ApiError::forbidden("you do not have the scope comments:write_internal"));
+    }
+
+    let decision = Decision {
+        reason: body.reason,
+        reason_code: body.reason_code,
+        message,
+    };
+
+    let results = db
+        .transaction({
+            let (user, status, visibility) = (user.clone(), body.status, body.comment_visibility);
+            move |conn| {
+                async move {
+                    let mut results = Vec::with_capacity(ids.len());
+
+                    for id in ids {
+                        let Some(application) = Application::fetch_by_id(conn, id).await? else {
+                            results.push(BulkUpdateResult::failed(id, "Not found"));
+                            continue;
+                        };
+
+                        // Every move is its own savepoint, so a failed one leaves the
+                        // others alone.
+                        let application = match transition(conn, &application, status, &user, decision.clone()).await {
+                            Ok(Some(application)) => application,
+                            Ok(None) => {
+                                results.push(BulkUpdateResult::failed(id, "the application was changed by someone else"));
+                                continue;
+                            }
+                            Err(err) if err.status.is_server_error() => anyhow::bail!("failed to move application {id}"),
+                            Err(err) => {
+                                results.push(BulkUpdateResult::failed(id, err.message));
+                                continue;
+                            }
+                        };
+
+                        if let Some(comment) = &comment {
+                            let comment = diesel::insert_into(schema::application_comments::table)
+                                .values(InsertComment {
+                                    application_id: id,
+                                    comment,
+                                    comment_html: &markdown::render(comment),
+                                    twitch_user_id: user.twitch_user_id,
+                                    twitch_username: &user.twitch_username,
+                                    twitch_display_name: &user.twitch_display_name,
+                                    twitch_profile_image_url: &user.twitch_profile_image_url,
+                                    visibility,
+                                    parent_id: None,
+                                })
+                                .returning(ApplicationComment::as_returning())
+                                .get_result(conn)
+                                .await?;
+
+                            mentions::record(conn, &comment).await?;
+                        }
+
+                        results.push(BulkUpdateResult {
+                            id,
+                            success: true,
+                            error: None,
+                            application: Some(application),
+                        });
+                    }
+
+                    anyhow::Ok(results)
+                }
+                .scope_boxed()
+            }
+        })
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to bulk update applications: {err:#}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(BulkUpdateResponse { results }))
+}
@@ -1,11 +1,23 @@
+use std::marker::PhantomData;
 use std::sync::Arc;
 
+use anyhow::Context;
 use axum::async_trait;
 use axum::http::request::Parts;
-use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use axum::http::{header, HeaderMap};
+use diesel::prelude::Insertable;
+use diesel::query_dsl::methods::{FilterDsl, FindDsl, SelectDsl};
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, SelectableHelper};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use jsonwebtoken::{Algorithm, Validation};
+use sha2::{Digest, Sha256};
 
 use super::error::ApiError;
-use crate::database::enums::TwitchAccountType;
+use super::permissions::{role_permissions, Permission, Scope};
+use crate::config::random_secret;
+use crate::database::enums::{TwitchAccountType, UserRole};
+use crate::database::schema;
+use crate::database::types::ApiToken;
 use crate::global::Global;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -16,29 +28,419 @@ pub struct User {
     pub twitch_profile_image_url: String,
     pub twitch_account_type: TwitchAccountType,
     pub follow_count: i32,
+    /// The scopes of the api token the request was made with. An api token
+    /// only gets the permissions of its user's role that are also in here.
+    #[serde(skip)]
+    pub api_token_scopes: Option<Vec<Permission>>,
+}
+
+/// The claims of a session token.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Claims {
+    #[serde(flatten)]
+    pub user: User,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+    /// The id of the session in the `sessions` table.
+    pub jti: String,
+    /// Set when an admin is viewing the api as this user. Such tokens are
+    /// read-only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<i32>,
+}
+
+/// The name of the cookie the website keeps the session token in.
+pub const SESSION_COOKIE: &str = "onlyfangs_session";
+
+/// The name of the cookie that ties a login to the browser that started it.
+pub const LOGIN_STATE_COOKIE: &str = "onlyfangs_login_state";
+
+/// Find the value of a cookie sent with a request.
+pub fn request_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(';'))
+        .find_map(|cookie| {
+            let (cookie_name, value) = cookie.trim().split_once('=')?;
+            (cookie_name == name).then_some(value)
+        })
+}
+
+/// Find the session token of a request, either from a `Bearer` authorization
+/// header or from the session cookie.
+fn request_token(req: &Parts) -> Option<&str> {
+    if let Some(header) = req.headers.get(header::AUTHORIZATION) {
+        let header = header.to_str().ok()?;
+
+        return match header.split_once(' ') {
+            Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => Some(token.trim()),
+            // Older clients send the token without a scheme.
+            None => Some(header),
+            Some(_) => None,
+        };
+    }
+
+    request_cookie(&req.headers, SESSION_COOKIE)
+}
+
+/// A `Set-Cookie` value that stores the session token in the browser.
+pub fn session_cookie(global: &Global, token: &str) -> String {
+    format!(
+        "{SESSION_COOKIE}={token}; Path={}; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+        cookie_path(global),
+        global.config.jwt_lifetime_secs,
+    )
+}
+
+/// A `Set-Cookie` value that removes the session cookie again.
+pub fn clear_session_cookie(global: &Global) -> String {
+    format!(
+        "{SESSION_COOKIE}=; Path={}; Max-Age=0; HttpOnly; Secure; SameSite=Lax",
+        cookie_path(global)
+    )
+}
+
+/// A `Set-Cookie` value that remembers the state of a login in the browser
+/// until it is completed.
+pub fn login_state_cookie(global: &Global, state: &str) -> String {
+    format!(
+        "{LOGIN_STATE_COOKIE}={state}; Path={}; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+        cookie_path(global),
+        global.config.twitch.login_state_ttl_secs,
+    )
+}
+
+/// A `Set-Cookie` value that removes the login state cookie again.
+pub fn clear_login_state_cookie(global: &Global) -> String {
+    format!(
+        "{LOGIN_STATE_COOKIE}=; Path={}; Max-Age=0; HttpOnly; Secure; SameSite=Lax",
+        cookie_path(global)
+    )
+}
+
+/// The cookie is scoped to the path the api is served under.
+fn cookie_path(global: &Global) -> String {
+    reqwest::Url::parse(&global.config.api_url)
+        .map(|url| url.path().to_owned())
+        .unwrap_or_else(|_| "/".to_owned())
+}
+
+#[derive(Insertable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::sessions)]
+struct InsertSession<'a> {
+    id: &'a str,
+    twitch_user_id: i32,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Claims {
+    pub fn new(user: User, session_id: String, global: &Global) -> Self {
+        let now = chrono::Utc::now().timestamp();
+
+        Claims {
+            user,
+            iss: global.config.api_url.clone(),
+            aud: global.config.app_url.clone(),
+            iat: now,
+            exp: now + global.config.jwt_lifetime_secs,
+            jti: session_id,
+            impersonated_by: None,
+        }
+    }
+
+    /// Start the session of these claims and sign a token for it.
+    async fn issue(&self, global: &Global, conn: &mut AsyncPgConnection) -> anyhow::Result<String> {
+        diesel::insert_into(schema::sessions::table)
+            .values(InsertSession {
+                id: &self.jti,
+                twitch_user_id: self.user.twitch_user_id,
+                expires_at: self.expires_at(),
+            })
+            .execute(conn)
+            .await
+            .context("insert session")?;
+
+        self.sign(global).context("sign token")
+    }
+
+    fn expires_at(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(self.exp, 0).unwrap_or_default()
+    }
+
+    /// Extend the session of these claims and sign a new token for it.
+    /// Impersonation sessions can not be extended, they end when they expire.
+    pub async fn refresh(self, global: &Global, conn: &mut AsyncPgConnection) -> anyhow::Result<String> {
+        anyhow::ensure!(self.impersonated_by.is_none(), "impersonation tokens can not be refreshed");
+
+        let claims = Claims::new(self.user, self.jti, global);
+
+        let updated = diesel::update(schema::sessions::dsl::sessions.find(&claims.jti))
+            .filter(schema::sessions::dsl::revoked_at.is_null())
+            .set(schema::sessions::dsl::expires_at.eq(claims.expires_at()))
+            .execute(conn)
+            .await
+            .context("update session")?;
+
+        anyhow::ensure!(updated == 1, "session no longer exists");
+
+        claims.sign(global).context("sign token")
+    }
+
+    /// Revoke the session of these claims.
+    pub async fn revoke(&self, global: &Global, conn: &mut AsyncPgConnection) -> anyhow::Result<()> {
+        diesel::update(schema::sessions::dsl::sessions.find(&self.jti))
+            .set(schema::sessions::dsl::revoked_at.eq(chrono::Utc::now()))
+            .execute(conn)
+            .await
+            .context("revoke session")?;
+
+        global.session_cache.remove(&self.jti);
+
+        Ok(())
+    }
+
+    /// Sign the claims into a session token.
+    pub fn sign(&self, global: &Global) -> Result<String, jsonwebtoken::errors::Error> {
+        global.jwt_keys.sign(self)
+    }
+
+    pub async fn extract(req: &mut Parts, global: &Arc<Global>) -> Result<Self, ApiError> {
+        let token = request_token(req).ok_or_else(ApiError::unauthorized)?;
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["exp", "iat", "iss", "aud"]);
+        validation.set_issuer(&[&global.config.api_url]);
+        validation.set_audience(&[&global.config.app_url]);
+
+        let Ok(claims) = global.jwt_keys.decode::<Claims>(token, &validation) else {
+            return Err(ApiError::unauthorized());
+        };
+
+        if claims.impersonated_by.is_some() && !req.method.is_safe() {
+            return Err(ApiError::forbidden("impersonation tokens are read-only"));
+        }
+
+        if !session_active(global, &claims.jti).await? {
+            return Err(ApiError::unauthorized());
+        }
+
+        Ok(claims)
+    }
+}
+
+async fn session_active(global: &Global, session_id: &str) -> Result<bool, ApiError> {
+    let session_id = session_id.to_owned();
+    if let Some(active) = global.session_cache.get(&session_id) {
+        return Ok(active);
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let active = schema::sessions::dsl::sessions
+        .find(&session_id)
+        .filter(schema::sessions::dsl::revoked_at.is_null())
+        .filter(schema::sessions::dsl::expires_at.gt(chrono::Utc::now()))
+        .select(schema::sessions::dsl::id)
+        .get_result::<String>(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch session: {err}");
+            ApiError::internal_server_error()
+        })?
+        .is_some();
+
+    global.session_cache.insert(session_id, active);
+
+    Ok(active)
+}
+
+/// Revoke every session of a user and return how many were revoked.
+pub async fn revoke_user_sessions(
+    global: &Global,
+    conn: &mut AsyncPgConnection,
+    twitch_user_id: i32,
+) -> anyhow::Result<usize> {
+    let revoked: Vec<String> = diesel::update(schema::sessions::table)
+        .filter(schema::sessions::dsl::twitch_user_id.eq(twitch_user_id))
+        .filter(schema::sessions::dsl::revoked_at.is_null())
+        .set(schema::sessions::dsl::revoked_at.eq(chrono::Utc::now()))
+        .returning(schema::sessions::dsl::id)
+        .get_results(conn)
+        .await
+        .context("revoke sessions")?;
+
+    for session_id in &revoked {
+        global.session_cache.remove(session_id);
+    }
+
+    Ok(revoked.len())
+}
+
+impl User {
+    /// Start a new session for this user and sign a token for it.
+    pub async fn issue_token(&self, global: &Global, conn: &mut AsyncPgConnection) -> anyhow::Result<String> {
+        Claims::new(self.clone(), random_secret(), global).issue(global, conn).await
+    }
+
+    /// Start a short-lived, read-only session that lets `admin` see the api
+    /// as this user.
+    pub async fn issue_impersonation_token(
+        &self,
+        global: &Global,
+        conn: &mut AsyncPgConnection,
+        admin: &User,
+    ) -> anyhow::Result<String> {
+        let mut claims = Claims::new(self.clone(), random_secret(), global);
+        claims.exp = claims.iat + global.config.impersonation_lifetime_secs;
+        claims.impersonated_by = Some(admin.twitch_user_id);
+        claims.issue(global, conn).await
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct TwitchUser(pub User);
 
+/// Authenticate a request with either a session token or an api token.
+async fn authenticate(req: &mut Parts, global: &Arc<Global>) -> Result<User, ApiError> {
+    if let Some(token) = request_token(req).filter(|token| token.starts_with(API_TOKEN_PREFIX)) {
+        let api_token = authenticate_api_token(global, token).await?;
+        return Ok(api_token.user());
+    }
+
+    Ok(Claims::extract(req, global).await?.user)
+}
+
 impl TwitchUser {
+    /// Api tokens are only accepted for reading here. Routes that act as the
+    /// user, like submitting an application or commenting, are not covered by
+    /// any scope.
     pub async fn extract(req: &mut Parts, global: &Arc<Global>) -> Result<Self, ApiError> {
-        let token = req
-            .headers
-            .get("Authorization")
-            .and_then(|header| header.to_str().ok())
-            .ok_or_else(ApiError::unauthorized)?;
-
-        let jwt_secret = DecodingKey::from_secret(global.config.jwt_secret.as_bytes());
-        let Ok(claims) = jsonwebtoken::decode::<User>(token, &jwt_secret, &Validation::new(Algorithm::HS256)) else {
-            return Err(ApiError::unauthorized());
-        };
+        let user = authenticate(req, global).await?;
+        if user.api_token_scopes.is_some() && !req.method.is_safe() {
+            return Err(ApiError::forbidden("api tokens can not be used for this endpoint"));
+        }
+
+        Ok(TwitchUser(user))
+    }
+}
+
+/// Fetch the role of a user, if they have one.
+pub async fn user_role(global: &Global, twitch_user_id: i32) -> Result<Option<UserRole>, ApiError> {
+    if let Some(role) = global.role_cache.get(&twitch_user_id) {
+        return Ok(role);
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let role = schema::user_roles::dsl::user_roles
+        .find(twitch_user_id)
+        .select(schema::user_roles::dsl::role)
+        .get_result::<UserRole>(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch user role: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    global.role_cache.insert(twitch_user_id, role);
+
+    Ok(role)
+}
+
+/// Whether a user with the given role has been granted the permission.
+pub fn role_has_permission(user: &User, role: UserRole, permission: Permission) -> bool {
+    // Api tokens can never do more than the user that created them.
+    role_permissions(role).contains(&permission)
+        && user
+            .api_token_scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.contains(&permission))
+}
+
+/// Whether the user has been granted the given permission.
+pub async fn has_permission(global: &Global, user: &User, permission: Permission) -> Result<bool, ApiError> {
+    Ok(user_role(global, user.twitch_user_id)
+        .await?
+        .is_some_and(|role| role_has_permission(user, role, permission)))
+}
+
+/// The prefix of every api token, so we can tell them apart from session
+/// tokens.
+pub const API_TOKEN_PREFIX: &str = "oft_";
+
+pub fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Look up an active api token and record that it was used.
+async fn authenticate_api_token(global: &Global, token: &str) -> Result<ApiToken, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let now = chrono::Utc::now();
 
-        Ok(TwitchUser(claims.claims))
+    diesel::update(schema::api_tokens::table)
+        .filter(schema::api_tokens::dsl::token_hash.eq(hash_api_token(token)))
+        .filter(schema::api_tokens::dsl::revoked_at.is_null())
+        .filter(
+            schema::api_tokens::dsl::expires_at
+                .is_null()
+                .or(schema::api_tokens::dsl::expires_at.gt(now)),
+        )
+        .set(schema::api_tokens::dsl::last_used_at.eq(now))
+        .returning(ApiToken::as_returning())
+        .get_result(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch api token: {err}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(ApiError::unauthorized)
+}
+
+impl ApiToken {
+    /// The user this token acts on behalf of.
+    pub fn user(&self) -> User {
+        User {
+            twitch_user_id: self.twitch_user_id,
+            twitch_username: self.twitch_username.clone(),
+            twitch_display_name: self.twitch_display_name.clone(),
+            twitch_profile_image_url: self.twitch_profile_image_url.clone(),
+            twitch_account_type: self.twitch_account_type,
+            follow_count: self.follow_count,
+            api_token_scopes: Some(self.scopes.iter().filter_map(|scope| Permission::from_name(scope)).collect()),
+        }
     }
 }
 
-pub struct TwitchAdminUser(pub User);
+/// A user that has been granted the permission `P`, along with the role
+/// that granted it.
+pub struct RequirePermission<P>(pub User, pub UserRole, pub PhantomData<P>);
+
+#[async_trait]
+impl axum::extract::FromRequestParts<Arc<Global>> for Claims {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(req: &mut Parts, global: &Arc<Global>) -> Result<Self, Self::Rejection> {
+        Claims::extract(req, global).await
+    }
+}
 
 #[async_trait]
 impl axum::extract::FromRequestParts<Arc<Global>> for TwitchUser {
@@ -50,16 +452,22 @@ impl axum::extract::FromRequestParts<Arc<Global>> for TwitchUser {
 }
 
 #[async_trait]
-impl axum::extract::FromRequestParts<Arc<Global>> for TwitchAdminUser {
+impl<P: Scope> axum::extract::FromRequestParts<Arc<Global>> for RequirePermission<P> {
     type Rejection = ApiError;
 
     async fn from_request_parts(req: &mut Parts, global: &Arc<Global>) -> Result<Self, Self::Rejection> {
-        let TwitchUser(twitch_user_id) = TwitchUser::extract(req, global).await?;
+        let user = authenticate(req, global).await?;
 
-        if !global.config.admin_twitch_ids.contains(&twitch_user_id.twitch_user_id) {
-            return Err(ApiError::unauthorized());
+        let missing = || ApiError::forbidden(format!("you do not have the scope {}", P::PERMISSION.name()));
+
+        let Some(role) = user_role(global, user.twitch_user_id).await? else {
+            return Err(missing());
+        };
+
+        if !role_has_permission(&user, role, P::PERMISSION) {
+            return Err(missing());
         }
 
-        Ok(TwitchAdminUser(twitch_user_id))
+        Ok(RequirePermission(user, role, PhantomData))
     }
 }
@@ -9,6 +9,10 @@ pub struct ApiError {
     #[serde(serialize_with = "serialize_status_code")]
     pub status: StatusCode,
     pub message: Cow<'static, str>,
+    /// Extra context for the client, e.g. the current state of a resource
+    /// that changed underneath it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
 
 fn serialize_status_code<S>(status: &StatusCode, serializer: S) -> Result<S::Ok, S::Error>
@@ -20,7 +24,11 @@ where
 
 impl ApiError {
     pub const fn new(status: StatusCode, message: Cow<'static, str>) -> Self {
-        ApiError { status, message }
+        ApiError {
+            status,
+            message,
+            data: None,
+        }
     }
 
     pub const fn internal_server_error() -> Self {
@@ -35,13 +43,30 @@ impl ApiError {
         ApiError::new(StatusCode::UNAUTHORIZED, Cow::Borrowed("Unauthorized"))
     }
 
-    pub const fn not_implemented() -> Self {
-        ApiError::new(StatusCode::NOT_IMPLEMENTED, Cow::Borrowed("Not implemented"))
-    }
-
     pub fn bad_request(message: impl Into<Cow<'static, str>>) -> Self {
         ApiError::new(StatusCode::BAD_REQUEST, message.into())
     }
+
+    pub fn forbidden(message: impl Into<Cow<'static, str>>) -> Self {
+        ApiError::new(StatusCode::FORBIDDEN, message.into())
+    }
+
+    pub fn conflict(message: impl Into<Cow<'static, str>>) -> Self {
+        ApiError::new(StatusCode::CONFLICT, message.into())
+    }
+
+    pub fn precondition_failed(message: impl Into<Cow<'static, str>>) -> Self {
+        ApiError::new(StatusCode::PRECONDITION_FAILED, message.into())
+    }
+
+    pub fn precondition_required(message: impl Into<Cow<'static, str>>) -> Self {
+        ApiError::new(StatusCode::PRECONDITION_REQUIRED, message.into())
+    }
+
+    pub fn with_data(mut self, data: impl serde::Serialize) -> Self {
+        self.data = serde_json::to_value(data).ok();
+        self
+    }
 }
 
 impl IntoResponse for ApiError {
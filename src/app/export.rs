@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use diesel::{ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use futures::StreamExt;
+
+use super::applications::{comment_count, sort_applications, ApplicationsSort, GetApplicationsRequest};
+use super::auth::RequirePermission;
+use super::error::ApiError;
+use super::permissions::scopes;
+use crate::database::enums::{ApplicationStatus, TwitchAccountType};
+use crate::database::schema;
+use crate::database::types::Application;
+use crate::global::Global;
+
+pub fn routes() -> Router<Arc<Global>> {
+    Router::new().route("/", get(export_applications))
+}
+
+/// How many rows may be waiting to be sent to the client before we stop
+/// reading from the database.
+const EXPORT_BUFFER_ROWS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    /// `format=` wins over the `Accept` header, csv is the default.
+    fn negotiate(format: Option<&str>, headers: &HeaderMap) -> Result<Self, ApiError> {
+        match format {
+            Some("csv") => return Ok(ExportFormat::Csv),
+            Some("ndjson") => return Ok(ExportFormat::Ndjson),
+            Some(format) => {
+                return Err(ApiError::bad_request(format!(
+                    "unknown format {format:?}, expected csv or ndjson"
+                )))
+            }
+            None => {}
+        }
+
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .unwrap_or_default();
+        if accept.contains("application/x-ndjson") || accept.contains("application/ndjson") {
+            Ok(ExportFormat::Ndjson)
+        } else {
+            Ok(ExportFormat::Csv)
+        }
+    }
+
+    const fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    const fn file_name(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "applications.csv",
+            ExportFormat::Ndjson => "applications.ndjson",
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ExportRequest {
+    format: Option<String>,
+}
+
+/// The columns of a csv export, the fields of [`ExportRow`] in order. They are
+/// written up front so an export without rows still has its header.
+const CSV_HEADER: [&str; 15] = [
+    "id",
+    "twitch_id",
+    "twitch_username",
+    "twitch_display_name",
+    "twitch_account_type",
+    "status",
+    "follow_count",
+    "reason",
+    "support_clip_url",
+    "created_at",
+    "updated_at",
+    "completed_at",
+    "comment_count",
+    "last_status_change_at",
+    "last_status_change_by",
+];
+
+#[derive(serde::Serialize)]
+struct ExportRow {
+    id: i32,
+    twitch_id: i32,
+    twitch_username: String,
+    twitch_display_name: String,
+    twitch_account_type: TwitchAccountType,
+    status: ApplicationStatus,
+    follow_count: i32,
+    reason: String,
+    support_clip_url: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    comment_count: i64,
+    last_status_change_at: Option<DateTime<Utc>>,
+    last_status_change_by: Option<String>,
+}
+
+impl ExportRow {
+    fn new(
+        (application, comment_count, last_status_change_at, last_status_change_by): (
+            Application,
+            Option<i64>,
+            Option<DateTime<Utc>>,
+            Option<String>,
+        ),
+    ) -> Self {
+        ExportRow {
+            id: application.id,
+            twitch_id: application.twitch_id,
+            twitch_username: application.twitch_username,
+            twitch_display_name: application.twitch_display_name,
+            twitch_account_type: application.twitch_account_type,
+            status: application.status,
+            follow_count: application.follow_count,
+            reason: application.reason,
+            support_clip_url: application.support_clip_url,
+            created_at: application.created_at,
+            updated_at: application.updated_at,
+            completed_at: application.completed_at,
+            comment_count: comment_count.unwrap_or_default(),
+            last_status_change_at,
+            last_status_change_by,
+        }
+    }
+
+    /// Spreadsheets run cells starting with these characters as formulas, so
+    /// prefix them with a quote to keep applicant text inert.
+    fn escape_formulas(mut self) -> Self {
+        for field in [
+            &mut self.twitch_username,
+            &mut self.twitch_display_name,
+            &mut self.reason,
+            &mut self.support_clip_url,
+        ] {
+            if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+                field.insert(0, '\'');
+            }
+        }
+
+        self
+    }
+}
+
+impl ExportFormat {
+    /// What the body starts with before any rows.
+    fn header(self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer.write_record(CSV_HEADER)?;
+                Ok(writer.into_inner()?)
+            }
+            ExportFormat::Ndjson => Ok(Vec::new()),
+        }
+    }
+
+    /// Turn a row into a chunk of the response body.
+    fn encode(self, row: ExportRow) -> anyhow::Result<Vec<u8>> {
+        match self {
+            ExportFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+                writer.serialize(row.escape_formulas())?;
+                Ok(writer.into_inner()?)
+            }
+            ExportFormat::Ndjson => {
+                let mut line = serde_json::to_vec(&row)?;
+                line.push(b'\n');
+                Ok(line)
+            }
+        }
+    }
+}
+
+/// GET /applications/export
+/// Stream every application matching the filters of GET /applications as csv
+/// or ndjson, picked by `format=` or the `Accept` header
+/// Scope: applications:export
+async fn export_applications(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsExport>,
+    headers: HeaderMap,
+    Query(request): Query<GetApplicationsRequest>,
+    Query(sort): Query<ApplicationsSort>,
+    Query(export): Query<ExportRequest>,
+) -> Result<Response, ApiError> {
+    let format = ExportFormat::negotiate(export.format.as_deref(), &headers)?;
+
+    let status_changes = || {
+        schema::application_status_events::table
+            .filter(schema::application_status_events::dsl::application_id.eq(schema::applications::dsl::id))
+            .order((
+                schema::application_status_events::dsl::created_at.desc(),
+                schema::application_status_events::dsl::id.desc(),
+            ))
+            .limit(1)
+    };
+
+    let query = sort_applications(&request, &sort, user.twitch_user_id, None)?.select((
+        Application::as_select(),
+        comment_count(true),
+        status_changes()
+            .select(schema::application_status_events::dsl::created_at)
+            .single_value(),
+        status_changes()
+            .select(schema::application_status_events::dsl::actor_twitch_username)
+            .single_value(),
+    ));
+
+    let mut db = global.database.get_owned().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<anyhow::Result<Vec<u8>>>(EXPORT_BUFFER_ROWS);
+
+    // The stream borrows the connection, so it lives in its own task that
+    // feeds the response body. The channel stops it from reading ahead of
+    // a slow client.
+    tokio::spawn(async move {
+        let mut rows = match query
+            .load_stream::<(Application, Option<i64>, Option<DateTime<Utc>>, Option<String>)>(&mut db)
+            .await
+        {
+            Ok(rows) => rows,
+            Err(err) => {
+                tracing::error!("Failed to export applications: {err}");
+                let _ = tx.send(Err(err.into())).await;
+                return;
+            }
+        };
+
+        if tx.send(format.header()).await.is_err() {
+            return;
+        }
+
+        while let Some(row) = rows.next().await {
+            let chunk = row
+                .map_err(anyhow::Error::from)
+                .and_then(|row| format.encode(ExportRow::new(row)));
+
+            if let Err(err) = &chunk {
+                tracing::error!("Failed to export applications: {err}");
+            }
+
+            let failed = chunk.is_err();
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let body = futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_owned()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", format.file_name()),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> ExportRow {
+        ExportRow {
+            id: 1,
+            twitch_id: 2,
+            twitch_username: "user".into(),
+            twitch_display_name: "=User".into(),
+            twitch_account_type: TwitchAccountType::Pleb,
+            status: ApplicationStatus::Pending,
+            follow_count: 3,
+            reason: "reason".into(),
+            support_clip_url: "https://clips.twitch.tv/x".into(),
+            created_at: DateTime::UNIX_EPOCH,
+            updated_at: DateTime::UNIX_EPOCH,
+            completed_at: None,
+            comment_count: 4,
+            last_status_change_at: None,
+            last_status_change_by: None,
+        }
+    }
+
+    #[test]
+    fn csv_header_matches_rows() {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.serialize(row()).unwrap();
+        let serialized = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+        let header = String::from_utf8(ExportFormat::Csv.header().unwrap()).unwrap();
+        assert_eq!(serialized.lines().next(), header.lines().next());
+    }
+
+    #[test]
+    fn csv_rows_have_no_header() {
+        let header = String::from_utf8(ExportFormat::Csv.header().unwrap()).unwrap();
+        let line = String::from_utf8(ExportFormat::Csv.encode(row()).unwrap()).unwrap();
+
+        assert_eq!(header.lines().count(), 1);
+        assert_eq!(line.lines().count(), 1);
+        assert!(line.starts_with("1,2,user,'=User,pleb,pending,3,"), "{line}");
+        assert!(ExportFormat::Ndjson.header().unwrap().is_empty());
+    }
+}
@@ -1,31 +1,108 @@
 use std::sync::Arc;
 
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use diesel::prelude::Insertable;
+use diesel::query_dsl::methods::{FindDsl, SelectDsl};
+use diesel::{ExpressionMethods, OptionalExtension, SelectableHelper};
+use diesel_async::RunQueryDsl;
 
-use super::auth::User;
+use super::auth::{
+    clear_login_state_cookie, clear_session_cookie, login_state_cookie, request_cookie, session_cookie, user_role, Claims,
+    User, LOGIN_STATE_COOKIE,
+};
 use super::error::ApiError;
+use super::permissions::{role_permissions, Permission};
+use crate::config::random_secret;
+use crate::database::enums::UserRole;
+use crate::database::schema;
+use crate::database::types::LoginState;
 use crate::global::Global;
+use crate::twitch::TwitchClient;
 
 pub fn routes() -> Router<Arc<Global>> {
-    Router::new().route("/", get(login)).route("/complete", post(login_complete))
+    Router::new()
+        .route("/", get(login))
+        .route("/complete", post(login_complete))
+        .route("/refresh", post(refresh))
+        .route("/logout", post(logout))
 }
 
+/// A json response that also updates cookies.
+type WithCookie<T, const N: usize = 1> = ([(header::HeaderName, String); N], Json<T>);
+
 #[derive(serde::Serialize)]
 struct LoginResponse {
     url: String,
 }
 
+#[derive(Insertable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::login_states)]
+struct InsertLoginState<'a> {
+    state: &'a str,
+    pkce_verifier: &'a str,
+    redirect_uri: &'a str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// GET /login
-/// Start the login process
+/// Start the login process. The state is also kept in a cookie, so only the
+/// browser that started a login can complete it
 /// Scope: none
-async fn login() -> Result<Json<LoginResponse>, ApiError> {
-    Err(ApiError::not_implemented())
+async fn login(State(global): State<Arc<Global>>) -> Result<WithCookie<LoginResponse>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let now = chrono::Utc::now();
+    let state = random_secret();
+    let pkce_verifier = random_secret();
+
+    // Opportunistically clean up logins that were never completed.
+    diesel::delete(schema::login_states::table)
+        .filter(schema::login_states::dsl::expires_at.lt(now - chrono::Duration::days(1)))
+        .execute(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to delete expired login states: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    diesel::insert_into(schema::login_states::table)
+        .values(InsertLoginState {
+            state: &state,
+            pkce_verifier: &pkce_verifier,
+            redirect_uri: &global.config.twitch.redirect_uri,
+            expires_at: now + chrono::Duration::seconds(global.config.twitch.login_state_ttl_secs),
+        })
+        .execute(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to insert login state: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    let url = TwitchClient::authorize_url(&global.config.twitch, &state, &TwitchClient::pkce_challenge(&pkce_verifier))
+        .map_err(|err| {
+            tracing::error!("Failed to build authorize url: {err:#}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok((
+        [(header::SET_COOKIE, login_state_cookie(&global, &state))],
+        Json(LoginResponse { url: url.into() }),
+    ))
 }
 
 #[derive(serde::Deserialize)]
 struct LoginCompleteRequest {
     code: String,
+    state: String,
+    redirect_uri: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -33,11 +110,196 @@ struct LoginCompleteResponse {
     token: String,
     user: User,
     is_admin: bool,
+    role: Option<UserRole>,
+    permissions: &'static [Permission],
 }
 
 /// POST /login/complete
 /// Complete the login process
 /// Scope: none
-async fn login_complete(body: Json<LoginCompleteRequest>) -> Result<Json<LoginCompleteResponse>, ApiError> {
-    Err(ApiError::not_implemented())
+async fn login_complete(
+    State(global): State<Arc<Global>>,
+    headers: HeaderMap,
+    Json(body): Json<LoginCompleteRequest>,
+) -> Result<WithCookie<LoginCompleteResponse, 2>, ApiError> {
+    let twitch_config = &global.config.twitch;
+
+    // Otherwise someone could get another browser to complete a login they
+    // started, logging it in as them.
+    if request_cookie(&headers, LOGIN_STATE_COOKIE) != Some(body.state.as_str()) {
+        return Err(ApiError::bad_request("login was started in another browser"));
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let login_state = schema::login_states::dsl::login_states
+        .find(&body.state)
+        .select(LoginState::as_select())
+        .get_result(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch login state: {err}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(|| ApiError::bad_request("invalid state"))?;
+
+    if login_state.used_at.is_some() {
+        return Err(ApiError::bad_request("state already used"));
+    }
+
+    if login_state.expires_at < chrono::Utc::now() {
+        return Err(ApiError::bad_request("state expired"));
+    }
+
+    if login_state.redirect_uri != twitch_config.redirect_uri
+        || body.redirect_uri.as_ref().is_some_and(|uri| *uri != login_state.redirect_uri)
+    {
+        return Err(ApiError::bad_request("redirect uri mismatch"));
+    }
+
+    // Mark the state as used before talking to Twitch so that two concurrent
+    // requests with the same state cannot both succeed.
+    let consumed = diesel::update(schema::login_states::dsl::login_states.find(&body.state))
+        .filter(schema::login_states::dsl::used_at.is_null())
+        .set(schema::login_states::dsl::used_at.eq(chrono::Utc::now()))
+        .execute(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to consume login state: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    if consumed == 0 {
+        return Err(ApiError::bad_request("state already used"));
+    }
+
+    let token = global
+        .twitch
+        .exchange_code(twitch_config, &body.code, &login_state.pkce_verifier)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to exchange code: {err:#}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(|| ApiError::bad_request("invalid code"))?;
+
+    let twitch_user = global
+        .twitch
+        .get_user(twitch_config, &token.access_token)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch twitch user: {err:#}");
+            ApiError::internal_server_error()
+        })?;
+
+    let follow_count = global
+        .twitch
+        .get_follower_count(twitch_config, &token.access_token, &twitch_user.id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch follower count: {err:#}");
+            ApiError::internal_server_error()
+        })?;
+
+    let twitch_user_id = twitch_user.id.parse().map_err(|err| {
+        tracing::error!("Failed to parse twitch user id {}: {err}", twitch_user.id);
+        ApiError::internal_server_error()
+    })?;
+
+    let user = User {
+        twitch_user_id,
+        twitch_account_type: twitch_user.account_type(),
+        twitch_username: twitch_user.login,
+        twitch_display_name: twitch_user.display_name,
+        twitch_profile_image_url: twitch_user.profile_image_url,
+        follow_count,
+        api_token_scopes: None,
+    };
+
+    let token = user.issue_token(&global, &mut db).await.map_err(|err| {
+        tracing::error!("Failed to issue token: {err:#}");
+        ApiError::internal_server_error()
+    })?;
+
+    let role = user_role(&global, user.twitch_user_id).await?;
+
+    // Keep the username of staff current so they can be @mentioned.
+    if role.is_some() {
+        diesel::update(schema::user_roles::dsl::user_roles.find(user.twitch_user_id))
+            .set(schema::user_roles::dsl::twitch_username.eq(&user.twitch_username))
+            .execute(&mut db)
+            .await
+            .map_err(|err| {
+                tracing::error!("Failed to update role username: {err}");
+                ApiError::internal_server_error()
+            })?;
+    }
+
+    Ok((
+        [
+            (header::SET_COOKIE, session_cookie(&global, &token)),
+            (header::SET_COOKIE, clear_login_state_cookie(&global)),
+        ],
+        Json(LoginCompleteResponse {
+            token,
+            user,
+            is_admin: role.is_some_and(|role| role.includes(UserRole::Admin)),
+            role,
+            permissions: role.map(role_permissions).unwrap_or_default(),
+        }),
+    ))
+}
+
+#[derive(serde::Serialize)]
+struct RefreshResponse {
+    token: String,
+}
+
+/// POST /login/refresh
+/// Re-issue a session token that is about to expire
+/// Scope: user
+async fn refresh(State(global): State<Arc<Global>>, claims: Claims) -> Result<WithCookie<RefreshResponse>, ApiError> {
+    if claims.impersonated_by.is_some() {
+        return Err(ApiError::forbidden("impersonation tokens can not be refreshed"));
+    }
+
+    if claims.exp - chrono::Utc::now().timestamp() > global.config.jwt_refresh_window_secs {
+        return Err(ApiError::bad_request("token is not due for refresh"));
+    }
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let token = claims.refresh(&global, &mut db).await.map_err(|err| {
+        tracing::error!("Failed to refresh token: {err:#}");
+        ApiError::internal_server_error()
+    })?;
+
+    Ok((
+        [(header::SET_COOKIE, session_cookie(&global, &token))],
+        Json(RefreshResponse { token }),
+    ))
+}
+
+/// POST /login/logout
+/// Revoke the current session
+/// Scope: user
+async fn logout(State(global): State<Arc<Global>>, claims: Claims) -> Result<WithCookie<()>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    claims.revoke(&global, &mut db).await.map_err(|err| {
+        tracing::error!("Failed to revoke session: {err:#}");
+        ApiError::internal_server_error()
+    })?;
+
+    Ok(([(header::SET_COOKIE, clear_session_cookie(&global))], Json(())))
 }
@@ -0,0 +1,266 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+/// Hosts whose bare URLs are turned into links.
+const AUTOLINK_HOSTS: &[&str] = &["twitch.tv", "youtube.com", "youtu.be"];
+
+/// Added to every link, they all point away from the site.
+const LINK_ATTRIBUTES: &str = r#" rel="nofollow noopener noreferrer" target="_blank""#;
+
+fn escape(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// Only web links are kept, anything else like `javascript:` is dropped.
+fn is_web_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+fn is_autolink_host(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host).to_ascii_lowercase();
+
+    AUTOLINK_HOSTS
+        .iter()
+        .any(|allowed| host == *allowed || host.strip_suffix(allowed).is_some_and(|sub| sub.ends_with('.')))
+}
+
+fn push_link(url: &str, text: &str, html: &mut String) {
+    html.push_str("<a href=\"");
+    escape(url, html);
+    html.push('"');
+    html.push_str(LINK_ATTRIBUTES);
+    html.push('>');
+    escape(text, html);
+    html.push_str("</a>");
+}
+
+/// Escape text, turning bare Twitch and YouTube URLs into links.
+fn text(text: &str, html: &mut String) {
+    let mut rest = text;
+
+    while let Some(start) = rest.find("http://").into_iter().chain(rest.find("https://")).min() {
+        let end = rest[start..].find(char::is_whitespace).map_or(rest.len(), |end| start + end);
+        // Punctuation at the end most likely belongs to the sentence.
+        let url = rest[start..end].trim_end_matches(['.', ',', '!', '?', ';', ':', ')', '\'', '"']);
+
+        escape(&rest[..start], html);
+        if is_autolink_host(url) {
+            push_link(url, url, html);
+        } else {
+            escape(url, html);
+        }
+
+        rest = &rest[start + url.len()..];
+    }
+
+    escape(rest, html);
+}
+
+/// Render the Markdown of a comment to HTML. Only a small subset is supported:
+/// paragraphs, emphasis, strikethrough, code, lists, quotes and web links.
+/// Raw HTML is escaped and headings are shown as plain paragraphs, so the
+/// output is safe to put on the page as is.
+pub fn render(source: &str) -> String {
+    let mut html = String::with_capacity(source.len() * 2);
+    // Whether each open link was rendered as a tag, so we know whether to
+    // close it.
+    let mut links = Vec::new();
+    // Text inside links and code is never autolinked.
+    let mut in_link_or_code = 0;
+    // The parser splits text at characters that could be Markdown, like the
+    // underscores in a URL, so join it back up before looking for links.
+    let mut pending = String::new();
+
+    for event in Parser::new_ext(source, Options::ENABLE_STRIKETHROUGH) {
+        if let Event::Text(content) = &event {
+            if in_link_or_code == 0 {
+                pending.push_str(content);
+                continue;
+            }
+        }
+
+        text(&pending, &mut html);
+        pending.clear();
+
+        match event {
+            Event::Start(tag) => match tag {
+                // Raw HTML is shown as the text it is.
+                Tag::Paragraph | Tag::Heading { .. } | Tag::HtmlBlock => html.push_str("<p>"),
+                Tag::BlockQuote(_) => html.push_str("<blockquote>"),
+                Tag::CodeBlock(kind) => {
+                    in_link_or_code += 1;
+                    html.push_str("<pre><code");
+                    if let CodeBlockKind::Fenced(lang) = kind {
+                        if let Some(lang) = lang.split_whitespace().next() {
+                            html.push_str(" class=\"language-");
+                            escape(lang, &mut html);
+                            html.push('"');
+                        }
+                    }
+                    html.push('>');
+                }
+                Tag::List(Some(1)) => html.push_str("<ol>"),
+                Tag::List(Some(start)) => html.push_str(&format!("<ol start=\"{start}\">")),
+                Tag::List(None) => html.push_str("<ul>"),
+                Tag::Item => html.push_str("<li>"),
+                Tag::Emphasis => html.push_str("<em>"),
+                Tag::Strong => html.push_str("<strong>"),
+                Tag::Strikethrough => html.push_str("<del>"),
+                Tag::Link { dest_url, .. } => {
+                    in_link_or_code += 1;
+                    let safe = is_web_url(&dest_url);
+                    if safe {
+                        html.push_str("<a href=\"");
+                        escape(&dest_url, &mut html);
+                        html.push('"');
+                        html.push_str(LINK_ATTRIBUTES);
+                        html.push('>');
+                    }
+                    links.push(safe);
+                }
+                // Images only keep their alt text.
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::HtmlBlock => html.push_str("</p>"),
+                TagEnd::BlockQuote(_) => html.push_str("</blockquote>"),
+                TagEnd::CodeBlock => {
+                    in_link_or_code -= 1;
+                    html.push_str("</code></pre>");
+                }
+                TagEnd::List(true) => html.push_str("</ol>"),
+                TagEnd::List(false) => html.push_str("</ul>"),
+                TagEnd::Item => html.push_str("</li>"),
+                TagEnd::Emphasis => html.push_str("</em>"),
+                TagEnd::Strong => html.push_str("</strong>"),
+                TagEnd::Strikethrough => html.push_str("</del>"),
+                TagEnd::Link => {
+                    in_link_or_code -= 1;
+                    if links.pop().unwrap_or_default() {
+                        html.push_str("</a>");
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(content) => escape(&content, &mut html),
+            Event::Code(content) => {
+                html.push_str("<code>");
+                escape(&content, &mut html);
+                html.push_str("</code>");
+            }
+            Event::Html(content) | Event::InlineHtml(content) => escape(&content, &mut html),
+            Event::SoftBreak | Event::HardBreak => html.push_str("<br>"),
+            Event::Rule => html.push_str("<hr>"),
+            _ => {}
+        }
+    }
+
+    text(&pending, &mut html);
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+
+    const ATTRIBUTES: &str = r#"rel="nofollow noopener noreferrer" target="_blank""#;
+
+    #[test]
+    fn formatting() {
+        assert_eq!(
+            render("**bold** _em_ ~~del~~ `co<de>`"),
+            "<p><strong>bold</strong> <em>em</em> <del>del</del> <code>co&lt;de&gt;</code></p>"
+        );
+        assert_eq!(render("# heading"), "<p>heading</p>");
+        assert_eq!(render("- a\n- b"), "<ul><li>a</li><li>b</li></ul>");
+    }
+
+    #[test]
+    fn drops_unsafe_links() {
+        for source in [
+            "[x](javascript:alert(1))",
+            "[x](JavaScript:alert(1))",
+            "[x](jav&#x61;script:alert(1))",
+            "[x](data:text/html,<script>alert(1)</script>)",
+            "[x](vbscript:msgbox(1))",
+            "[x](//example.com)",
+        ] {
+            let html = render(source);
+            assert!(!html.contains("<a"), "{source:?} rendered {html:?}");
+            assert!(html.starts_with("<p>x"), "{source:?} rendered {html:?}");
+        }
+    }
+
+    #[test]
+    fn escapes_raw_html() {
+        assert_eq!(
+            render("<script>alert(1)</script>"),
+            "<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>"
+        );
+        assert_eq!(
+            render("hi <img src=x onerror=alert(1)> there"),
+            "<p>hi &lt;img src=x onerror=alert(1)&gt; there</p>"
+        );
+        assert!(!render("<div onclick=\"alert(1)\">\n\nx\n\n</div>").contains("<div"));
+    }
+
+    #[test]
+    fn escapes_quotes_in_links() {
+        assert_eq!(
+            render(r#"[x](https://example.com/"onmouseover="alert(1))"#),
+            format!(r#"<p><a href="https://example.com/&quot;onmouseover=&quot;alert(1)" {ATTRIBUTES}>x</a></p>"#)
+        );
+        assert_eq!(
+            render(r#"[x](<https://example.com/" onclick="a>)"#),
+            format!(r#"<p><a href="https://example.com/&quot; onclick=&quot;a" {ATTRIBUTES}>x</a></p>"#)
+        );
+    }
+
+    #[test]
+    fn escapes_code_block_language() {
+        assert_eq!(
+            render("```js\" onclick=\"alert(1)\n<b>code</b>\n```"),
+            r#"<pre><code class="language-js&quot;">&lt;b&gt;code&lt;/b&gt;
+</code></pre>"#
+        );
+    }
+
+    #[test]
+    fn images_keep_only_their_alt_text() {
+        assert_eq!(
+            render("![<img src=x onerror=alert(1)>](https://example.com/a.png)"),
+            "<p>&lt;img src=x onerror=alert(1)&gt;</p>"
+        );
+        assert_eq!(render(r#"![alt "text"](javascript:alert(1))"#), "<p>alt &quot;text&quot;</p>");
+    }
+
+    #[test]
+    fn autolinks_twitch_and_youtube() {
+        assert_eq!(
+            render("see https://www.twitch.tv/some_user/clip/a_b_c."),
+            format!(
+                r#"<p>see <a href="https://www.twitch.tv/some_user/clip/a_b_c" {ATTRIBUTES}>https://www.twitch.tv/some_user/clip/a_b_c</a>.</p>"#
+            )
+        );
+        assert_eq!(render("https://twitch.tv.evil.com/x"), "<p>https://twitch.tv.evil.com/x</p>");
+        assert_eq!(render("https://eviltwitch.tv/x"), "<p>https://eviltwitch.tv/x</p>");
+        assert_eq!(
+            render("`https://youtu.be/x` [https://youtu.be/y](https://example.com)"),
+            format!(
+                r#"<p><code>https://youtu.be/x</code> <a href="https://example.com" {ATTRIBUTES}>https://youtu.be/y</a></p>"#
+            )
+        );
+    }
+}
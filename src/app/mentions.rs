@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, SelectableHelper};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use super::applications::{decode_cursor, encode_cursor, PageRequest};
+use super::auth::RequirePermission;
+use super::error::ApiError;
+use super::permissions::scopes;
+use crate::database::schema;
+use crate::database::types::ApplicationComment;
+use crate::global::Global;
+
+pub fn routes() -> Router<Arc<Global>> {
+    Router::new().route("/", get(get_mentions))
+}
+
+fn is_username_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The usernames mentioned with `@username` in a comment, lowercased like
+/// Twitch logins. An `@` right after a word, like in an email address, is not
+/// a mention.
+fn parse(comment: &str) -> Vec<String> {
+    let mut usernames: Vec<String> = Vec::new();
+    let mut parts = comment.split('@');
+    let mut before = parts.next().unwrap_or_default();
+
+    for part in parts {
+        let attached = before.chars().next_back().is_some_and(is_username_char);
+        let username = part
+            .chars()
+            .take_while(|c| is_username_char(*c))
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if !attached && (1..=25).contains(&username.len()) && !usernames.contains(&username) {
+            usernames.push(username);
+        }
+
+        before = part;
+    }
+
+    usernames
+}
+
+/// Record which staff are mentioned in a new or edited comment, returning
+/// their usernames. Mentions an edit removed are dropped. Mentions of anyone
+/// without a role are ignored, and applicants do not get to ping reviewers at
+/// all.
+pub async fn record(conn: &mut AsyncPgConnection, comment: &ApplicationComment) -> QueryResult<Vec<String>> {
+    diesel::delete(schema::comment_mentions::table)
+        .filter(schema::comment_mentions::dsl::comment_id.eq(comment.id))
+        .execute(conn)
+        .await?;
+
+    let usernames = parse(&comment.comment);
+    if usernames.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let applicant: i32 = schema::applications::table
+        .find(comment.application_id)
+        .select(schema::applications::dsl::twitch_id)
+        .get_result(conn)
+        .await?;
+    if applicant == comment.twitch_user_id {
+        return Ok(Vec::new());
+    }
+
+    let staff: Vec<(i32, Option<String>)> = schema::user_roles::table
+        .filter(schema::user_roles::dsl::twitch_username.eq_any(&usernames))
+        .filter(schema::user_roles::dsl::twitch_user_id.ne(comment.twitch_user_id))
+        .select((
+            schema::user_roles::dsl::twitch_user_id,
+            schema::user_roles::dsl::twitch_username,
+        ))
+        .load(conn)
+        .await?;
+
+    diesel::insert_into(schema::comment_mentions::table)
+        .values(
+            staff
+                .iter()
+                .map(|(twitch_user_id, _)| {
+                    (
+                        schema::comment_mentions::dsl::comment_id.eq(comment.id),
+                        schema::comment_mentions::dsl::twitch_user_id.eq(*twitch_user_id),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await?;
+
+    Ok(staff.into_iter().filter_map(|(_, username)| username).collect())
+}
+
+/// The position of the last comment of a page.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct MentionCursor {
+    id: i32,
+}
+
+#[derive(serde::Serialize)]
+struct GetMentionsResponse {
+    comments: Vec<ApplicationComment>,
+    next_cursor: Option<String>,
+}
+
+/// GET /applications/mentions
+/// Get the comments mentioning the current user, newest first
+/// Scope: comments:read
+async fn get_mentions(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, ..): RequirePermission<scopes::CommentsRead>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<GetMentionsResponse>, ApiError> {
+    let limit = page.limit()?;
+    let cursor = page.cursor().map(decode_cursor::<MentionCursor>).transpose()?;
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let mut query = schema::comment_mentions::table
+        .inner_join(schema::application_comments::table)
+        .filter(schema::comment_mentions::dsl::twitch_user_id.eq(user.twitch_user_id))
+        .filter(schema::application_comments::dsl::deleted_at.is_null())
+        .into_boxed();
+
+    if let Some(cursor) = cursor {
+        query = query.filter(schema::application_comments::dsl::id.lt(cursor.id));
+    }
+
+    // Fetch one more than we return so we know if there is another page.
+    let mut comments = query
+        .order(schema::application_comments::dsl::id.desc())
+        .limit(limit + 1)
+        .select(ApplicationComment::as_select())
+        .load(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch mentions: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    let next_cursor = if comments.len() as i64 > limit {
+        comments.truncate(limit as usize);
+        comments
+            .last()
+            .map(|comment| encode_cursor(&MentionCursor { id: comment.id }))
+    } else {
+        None
+    };
+
+    Ok(Json(GetMentionsResponse { comments, next_cursor }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn finds_mentions() {
+        assert_eq!(parse("hey @Reviewer_1, can you look?"), ["reviewer_1"]);
+        assert_eq!(parse("@a @b"), ["a", "b"]);
+        assert_eq!(parse("no mentions here"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn stops_at_punctuation() {
+        assert_eq!(parse("thanks @someone!"), ["someone"]);
+        assert_eq!(parse("(@someone) @other. @third's"), ["someone", "other", "third"]);
+        assert_eq!(parse("@someone-else"), ["someone"]);
+    }
+
+    #[test]
+    fn skips_duplicates() {
+        assert_eq!(parse("@Someone and @someone and @SOMEONE"), ["someone"]);
+    }
+
+    #[test]
+    fn ignores_email_addresses() {
+        assert_eq!(parse("mail me@example.com or @me"), ["me"]);
+        assert_eq!(parse("user_1@example.com"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn enforces_username_length() {
+        assert_eq!(parse("@ alone and @!"), Vec::<String>::new());
+        assert_eq!(parse(&format!("@{}", "a".repeat(25))), ["a".repeat(25)]);
+        assert_eq!(parse(&format!("@{}", "a".repeat(26))), Vec::<String>::new());
+    }
+}
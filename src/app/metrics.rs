@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use diesel::sql_types::{BigInt, Double, Nullable};
+use diesel::QueryableByName;
+use diesel_async::RunQueryDsl;
+
+use super::auth::RequirePermission;
+use super::error::ApiError;
+use super::permissions::scopes;
+use crate::database::enums::ApplicationStatus;
+use crate::database::schema;
+use crate::global::Global;
+
+pub fn routes() -> Router<Arc<Global>> {
+    Router::new().route("/", get(get_metrics))
+}
+
+/// How long applications stay in a status before they are moved on.
+#[derive(serde::Serialize, QueryableByName)]
+struct StatusDurations {
+    #[diesel(sql_type = schema::sql_types::ApplicationStatus)]
+    status: ApplicationStatus,
+    /// How many times an application left this status.
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+    #[diesel(sql_type = Double)]
+    average_seconds: f64,
+    #[diesel(sql_type = Double)]
+    median_seconds: f64,
+}
+
+/// How long it takes from submitting an application until it is completed.
+#[derive(serde::Serialize, QueryableByName)]
+struct CompletionDurations {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+    #[diesel(sql_type = Nullable<Double>)]
+    average_seconds: Option<f64>,
+    #[diesel(sql_type = Nullable<Double>)]
+    median_seconds: Option<f64>,
+}
+
+#[derive(serde::Serialize)]
+struct GetMetricsResponse {
+    time_in_status: Vec<StatusDurations>,
+    time_to_completion: CompletionDurations,
+}
+
+// Each event closes the stay in its from status, which started at the previous
+// event or when the application was submitted.
+const TIME_IN_STATUS_QUERY: &str = r#"
+WITH stays AS (
+    SELECT
+        e.from_status AS status,
+        EXTRACT(EPOCH FROM e.created_at - COALESCE(
+            LAG(e.created_at) OVER (PARTITION BY e.application_id ORDER BY e.created_at, e.id),
+            a.created_at
+        ))::FLOAT8 AS seconds
+    FROM application_status_events e
+    JOIN applications a ON a.id = e.application_id
+)
+SELECT
+    status,
+    COUNT(*) AS count,
+    AVG(seconds) AS average_seconds,
+    PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY seconds) AS median_seconds
+FROM stays
+GROUP BY status
+ORDER BY status
+"#;
+
+const TIME_TO_COMPLETION_QUERY: &str = r#"
+SELECT
+    COUNT(*) AS count,
+    AVG(seconds) AS average_seconds,
+    PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY seconds) AS median_seconds
+FROM (
+    SELECT EXTRACT(EPOCH FROM completed_at - created_at)::FLOAT8 AS seconds
+    FROM applications
+    WHERE completed_at IS NOT NULL
+) AS completed
+"#;
+
+/// GET /applications/metrics
+/// Get how long applications spend in each status
+/// Scope: applications:read
+async fn get_metrics(
+    State(global): State<Arc<Global>>,
+    _: RequirePermission<scopes::ApplicationsRead>,
+) -> Result<Json<GetMetricsResponse>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let time_in_status = diesel::sql_query(TIME_IN_STATUS_QUERY).load(&mut db).await.map_err(|err| {
+        tracing::error!("Failed to fetch time in status: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let time_to_completion = diesel::sql_query(TIME_TO_COMPLETION_QUERY)
+        .get_result(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch time to completion: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(GetMetricsResponse {
+        time_in_status,
+        time_to_completion,
+    }))
+}
@@ -10,17 +10,30 @@ use tower_http::services::{ServeDir, ServeFile};
 
 use crate::global::Global;
 
+mod admin;
 mod application;
 mod applications;
 mod auth;
 mod error;
+mod export;
 mod login;
+mod markdown;
+mod mentions;
+mod metrics;
+mod permissions;
+mod search;
+mod templates;
+mod tokens;
+mod views;
 
 fn api_routes(global: Arc<Global>) -> Router {
     Router::new()
         .nest("/login", login::routes())
+        .nest("/admin", admin::routes())
         .nest("/applications", applications::routes())
         .nest("/application", application::routes())
+        .nest("/templates", templates::routes())
+        .nest("/tokens", tokens::routes())
         .with_state(global)
         .fallback(not_found)
 }
@@ -0,0 +1,102 @@
+use crate::database::enums::UserRole;
+
+/// A scope that can be required by the
+/// [`RequirePermission`](super::auth::RequirePermission) extractor.
+pub trait Scope: Send + Sync {
+    const PERMISSION: Permission;
+}
+
+/// Declares the [`Permission`] enum together with a marker type per
+/// permission in the [`scopes`] module, so routes can name the permission they
+/// need in their extractor.
+macro_rules! permissions {
+    ($($variant:ident => $name:literal),*$(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+        pub enum Permission {
+            $(
+                #[serde(rename = $name)]
+                $variant,
+            )*
+        }
+
+        impl Permission {
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(
+                        $name => Some(Permission::$variant),
+                    )*
+                    _ => None,
+                }
+            }
+
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(
+                        Permission::$variant => $name,
+                    )*
+                }
+            }
+        }
+
+        pub mod scopes {
+            $(
+                pub struct $variant;
+
+                impl super::Scope for $variant {
+                    const PERMISSION: super::Permission = super::Permission::$variant;
+                }
+            )*
+        }
+    };
+}
+
+permissions! {
+    ApplicationsRead => "applications:read",
+    ApplicationsDecide => "applications:decide",
+    ApplicationsExport => "applications:export",
+    CommentsModerate => "comments:moderate",
+    CommentsRead => "comments:read",
+    CommentsWrite => "comments:write",
+    CommentsWriteInternal => "comments:write_internal",
+    RolesManage => "roles:manage",
+    SessionsManage => "sessions:manage",
+    TemplatesManage => "templates:manage",
+    TokensManage => "tokens:manage",
+    UsersImpersonate => "users:impersonate",
+    ViewsManage => "views:manage",
+}
+
+const VIEWER: &[Permission] = &[Permission::ApplicationsRead, Permission::CommentsRead];
+
+const REVIEWER: &[Permission] = &[
+    Permission::ApplicationsRead,
+    Permission::ApplicationsDecide,
+    Permission::CommentsRead,
+    Permission::CommentsWrite,
+    Permission::CommentsWriteInternal,
+];
+
+const ADMIN: &[Permission] = &[
+    Permission::ApplicationsRead,
+    Permission::ApplicationsDecide,
+    Permission::ApplicationsExport,
+    Permission::CommentsModerate,
+    Permission::CommentsRead,
+    Permission::CommentsWrite,
+    Permission::CommentsWriteInternal,
+    Permission::RolesManage,
+    Permission::SessionsManage,
+    Permission::TemplatesManage,
+    Permission::TokensManage,
+    Permission::UsersImpersonate,
+    Permission::ViewsManage,
+];
+
+/// The permissions granted by a role.
+pub const fn role_permissions(role: UserRole) -> &'static [Permission] {
+    match role {
+        UserRole::Owner | UserRole::Admin => ADMIN,
+        UserRole::Reviewer => REVIEWER,
+        UserRole::Viewer => VIEWER,
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use diesel::dsl::sql;
+use diesel::expression::BoxableExpression;
+use diesel::pg::Pg;
+use diesel::sql_types::{Array, Bool, Float, Integer, Nullable, Text};
+use diesel::QueryableByName;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::database::schema;
+
+pub const MAX_QUERY_LENGTH: usize = 200;
+
+pub type ApplicationsExpression<ST> = Box<dyn BoxableExpression<schema::applications::table, Pg, SqlType = ST>>;
+
+// The rank of an application is the rank of its own text plus the best rank of
+// any of its comments. The query is bound once in each `websearch_to_tsquery`.
+const RANK_START: &str = "(ts_rank(applications.search_vector, websearch_to_tsquery('english', ";
+const RANK_MIDDLE: &str = ")) + COALESCE((SELECT MAX(ts_rank(c.search_vector, websearch_to_tsquery('english', ";
+const RANK_END: &str = "))) FROM application_comments c WHERE c.application_id = applications.id), 0))";
+
+/// Applications whose username, reason or comments match the search query.
+pub fn matches(q: &str) -> ApplicationsExpression<Bool> {
+    Box::new(
+        sql::<Bool>("(applications.search_vector @@ websearch_to_tsquery('english', ")
+            .bind::<Text, _>(q.to_owned())
+            .sql(") OR applications.twitch_username ILIKE ")
+            .bind::<Text, _>(format!("%{}%", q))
+            .sql(
+                " OR EXISTS (SELECT 1 FROM application_comments c WHERE c.application_id = applications.id AND c.search_vector @@ websearch_to_tsquery('english', ",
+            )
+            .bind::<Text, _>(q.to_owned())
+            .sql(")))"),
+    )
+}
+
+/// How relevant an application is to the search query, higher is better.
+pub fn rank(q: &str) -> ApplicationsExpression<Float> {
+    Box::new(
+        sql::<Float>(RANK_START)
+            .bind::<Text, _>(q.to_owned())
+            .sql(RANK_MIDDLE)
+            .bind::<Text, _>(q.to_owned())
+            .sql(RANK_END),
+    )
+}
+
+/// Applications ranked after the given position when sorting by relevance.
+pub fn ranked_after(q: &str, rank: f32, id: i32) -> ApplicationsExpression<Bool> {
+    Box::new(
+        sql::<Bool>("(")
+            .sql(RANK_START)
+            .bind::<Text, _>(q.to_owned())
+            .sql(RANK_MIDDLE)
+            .bind::<Text, _>(q.to_owned())
+            .sql(RANK_END)
+            .sql(", applications.id) < (")
+            .bind::<Float, _>(rank)
+            .sql(", ")
+            .bind::<Integer, _>(id)
+            .sql(")"),
+    )
+}
+
+/// Highlighted fragments of the text that matched the search query. Matches
+/// are wrapped in `<mark>` and the rest of the text is html escaped.
+#[derive(Debug, serde::Serialize, QueryableByName)]
+pub struct Snippets {
+    #[diesel(sql_type = Integer)]
+    #[serde(skip)]
+    id: i32,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub reason: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub comment: Option<String>,
+}
+
+const SNIPPETS_QUERY: &str = r#"
+SELECT
+    a.id,
+    CASE WHEN to_tsvector('english', a.reason) @@ websearch_to_tsquery('english', $1) THEN ts_headline(
+        'english',
+        replace(replace(replace(a.reason, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+        websearch_to_tsquery('english', $1),
+        'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, FragmentDelimiter=" ... "'
+    ) END AS reason,
+    (
+        SELECT ts_headline(
+            'english',
+            replace(replace(replace(c.comment, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+            websearch_to_tsquery('english', $1),
+            'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, FragmentDelimiter=" ... "'
+        )
+        FROM application_comments c
+        WHERE c.application_id = a.id AND c.search_vector @@ websearch_to_tsquery('english', $1)
+        ORDER BY ts_rank(c.search_vector, websearch_to_tsquery('english', $1)) DESC, c.id
+        LIMIT 1
+    ) AS comment
+FROM applications a
+WHERE a.id = ANY($2)
+"#;
+
+/// Fetch the snippets for a page of search results, keyed by application id.
+pub async fn snippets(conn: &mut AsyncPgConnection, q: &str, ids: &[i32]) -> anyhow::Result<HashMap<i32, Snippets>> {
+    let snippets: Vec<Snippets> = diesel::sql_query(SNIPPETS_QUERY)
+        .bind::<Text, _>(q)
+        .bind::<Array<Integer>, _>(ids)
+        .load(conn)
+        .await?;
+
+    Ok(snippets.into_iter().map(|snippet| (snippet.id, snippet)).collect())
+}
@@ -0,0 +1,399 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use diesel::prelude::{AsChangeset, Insertable};
+use diesel::query_dsl::methods::{FilterDsl, FindDsl, OrderDsl, SelectDsl};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::{ExpressionMethods, OptionalExtension, SelectableHelper};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use super::auth::{RequirePermission, User};
+use super::error::ApiError;
+use super::permissions::scopes;
+use crate::database::enums::ApplicationStatus;
+use crate::database::schema;
+use crate::database::types::{Application, MessageTemplate};
+use crate::global::Global;
+
+pub fn routes() -> Router<Arc<Global>> {
+    Router::new()
+        .route("/", get(get_templates).post(create_template))
+        .route("/:id", get(get_template).put(update_template).delete(delete_template))
+}
+
+/// The placeholders a message can use, written as `{name}`. `{{` and `}}` are
+/// literal braces.
+const PLACEHOLDERS: &[&str] = &["display_name", "username", "clip_url", "status", "reviewer"];
+
+/// The longest a message can be, both as written and once it is filled in,
+/// since it ends up as a comment.
+const MAX_MESSAGE_LENGTH: usize = 1000;
+
+/// Replace every placeholder in a message with its value.
+fn expand(message: &str, value: impl Fn(&str) -> Option<String>) -> Result<String, ApiError> {
+    let mut expanded = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("{{") {
+            expanded.push('{');
+            rest = after;
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix("}}") {
+            expanded.push('}');
+            rest = after;
+            continue;
+        }
+
+        if rest.starts_with('}') {
+            return Err(ApiError::bad_request("unmatched }, use }} for a literal }"));
+        }
+
+        let end = rest
+            .find('}')
+            .ok_or_else(|| ApiError::bad_request("unclosed placeholder, use {{ for a literal {"))?;
+        let name = &rest[1..end];
+        let value = value(name).ok_or_else(|| {
+            ApiError::bad_request(format!(
+                "unknown placeholder {{{name}}}, expected one of {}",
+                PLACEHOLDERS.join(", ")
+            ))
+        })?;
+
+        expanded.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+/// Make sure a message only uses known placeholders.
+fn validate_message(message: &str) -> Result<(), ApiError> {
+    if message.trim().is_empty() || message.len() > MAX_MESSAGE_LENGTH {
+        return Err(ApiError::bad_request(format!(
+            "message must be between 1 and {MAX_MESSAGE_LENGTH} characters"
+        )));
+    }
+
+    expand(message, |name| PLACEHOLDERS.contains(&name).then(String::new))?;
+
+    Ok(())
+}
+
+/// Fill in a message for the applicant of an application that is being moved
+/// to `status` by `reviewer`. Fails if the filled in message is too long to
+/// post as a comment.
+pub fn render(
+    message: &str,
+    application: &Application,
+    status: ApplicationStatus,
+    reviewer: &User,
+) -> Result<String, ApiError> {
+    let rendered = expand(message, |name| match name {
+        "display_name" => Some(application.twitch_display_name.clone()),
+        "username" => Some(application.twitch_username.clone()),
+        "clip_url" => Some(application.support_clip_url.clone()),
+        "status" => Some(status.to_string()),
+        "reviewer" => Some(reviewer.twitch_display_name.clone()),
+        _ => None,
+    })?;
+
+    if rendered.len() > MAX_MESSAGE_LENGTH {
+        return Err(ApiError::bad_request(format!(
+            "message is longer than {MAX_MESSAGE_LENGTH} characters once its placeholders are filled in"
+        )));
+    }
+
+    Ok(rendered)
+}
+
+/// Work out the message to send the applicant with a decision: the given
+/// message if there is one, otherwise the template of the reason code.
+/// A reason code always has to name an existing template.
+pub async fn resolve_message(
+    conn: &mut AsyncPgConnection,
+    reason_code: Option<&str>,
+    message: Option<String>,
+) -> Result<Option<String>, ApiError> {
+    let template = match reason_code {
+        Some(code) => Some(
+            schema::message_templates::table
+                .filter(schema::message_templates::dsl::code.eq(code))
+                .select(MessageTemplate::as_select())
+                .get_result(conn)
+                .await
+                .optional()
+                .map_err(|err| {
+                    tracing::error!("Failed to fetch message template: {err}");
+                    ApiError::internal_server_error()
+                })?
+                .ok_or_else(|| ApiError::bad_request(format!("unknown reason code {code:?}")))?,
+        ),
+        None => None,
+    };
+
+    match message.filter(|message| !message.trim().is_empty()) {
+        Some(message) => {
+            validate_message(&message)?;
+            Ok(Some(message))
+        }
+        None => Ok(template.map(|template| template.body)),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SaveTemplateRequest {
+    code: String,
+    name: String,
+    body: String,
+}
+
+impl SaveTemplateRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        if self.code.is_empty()
+            || self.code.len() > 50
+            || !self
+                .code
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_')
+        {
+            return Err(ApiError::bad_request(
+                "code must be between 1 and 50 lowercase letters, digits or underscores",
+            ));
+        }
+
+        if self.name.is_empty() || self.name.len() > 100 {
+            return Err(ApiError::bad_request("name must be between 1 and 100 characters"));
+        }
+
+        validate_message(&self.body)
+    }
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::message_templates)]
+struct InsertMessageTemplate<'a> {
+    code: &'a str,
+    name: &'a str,
+    body: &'a str,
+}
+
+fn save_error(err: DieselError) -> ApiError {
+    match err {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            ApiError::conflict("a template with this code already exists")
+        }
+        err => {
+            tracing::error!("Failed to save message template: {err}");
+            ApiError::internal_server_error()
+        }
+    }
+}
+
+/// GET /templates
+/// Get every message template
+/// Scope: applications:decide
+async fn get_templates(
+    State(global): State<Arc<Global>>,
+    _: RequirePermission<scopes::ApplicationsDecide>,
+) -> Result<Json<Vec<MessageTemplate>>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let templates = schema::message_templates::table
+        .order(schema::message_templates::dsl::code)
+        .select(MessageTemplate::as_select())
+        .load(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch message templates: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(templates))
+}
+
+/// GET /templates/:id
+/// Get a message template by id
+/// Scope: applications:decide
+async fn get_template(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    _: RequirePermission<scopes::ApplicationsDecide>,
+) -> Result<Json<MessageTemplate>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    schema::message_templates::table
+        .find(id)
+        .select(MessageTemplate::as_select())
+        .get_result(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch message template: {err}");
+            ApiError::internal_server_error()
+        })?
+        .map(Json)
+        .ok_or_else(ApiError::not_found)
+}
+
+/// POST /templates
+/// Create a message template, its code is the reason code decisions refer to
+/// Scope: templates:manage
+async fn create_template(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, ..): RequirePermission<scopes::TemplatesManage>,
+    Json(body): Json<SaveTemplateRequest>,
+) -> Result<Json<MessageTemplate>, ApiError> {
+    body.validate()?;
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let template = diesel::insert_into(schema::message_templates::table)
+        .values((
+            InsertMessageTemplate {
+                code: &body.code,
+                name: &body.name,
+                body: &body.body,
+            },
+            schema::message_templates::dsl::created_by.eq(user.twitch_user_id),
+        ))
+        .returning(MessageTemplate::as_returning())
+        .get_result(&mut db)
+        .await
+        .map_err(save_error)?;
+
+    Ok(Json(template))
+}
+
+/// PUT /templates/:id
+/// Replace the code, name and body of a message template
+/// Scope: templates:manage
+async fn update_template(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    _: RequirePermission<scopes::TemplatesManage>,
+    Json(body): Json<SaveTemplateRequest>,
+) -> Result<Json<MessageTemplate>, ApiError> {
+    body.validate()?;
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    diesel::update(schema::message_templates::table.find(id))
+        .set((
+            InsertMessageTemplate {
+                code: &body.code,
+                name: &body.name,
+                body: &body.body,
+            },
+            schema::message_templates::dsl::updated_at.eq(chrono::Utc::now()),
+        ))
+        .returning(MessageTemplate::as_returning())
+        .get_result(&mut db)
+        .await
+        .optional()
+        .map_err(save_error)?
+        .map(Json)
+        .ok_or_else(ApiError::not_found)
+}
+
+/// DELETE /templates/:id
+/// Delete a message template, decisions keep the reason code they were made
+/// with
+/// Scope: templates:manage
+async fn delete_template(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    _: RequirePermission<scopes::TemplatesManage>,
+) -> Result<Json<MessageTemplate>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    diesel::delete(schema::message_templates::table.find(id))
+        .returning(MessageTemplate::as_returning())
+        .get_result(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to delete message template: {err}");
+            ApiError::internal_server_error()
+        })?
+        .map(Json)
+        .ok_or_else(ApiError::not_found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(message: &str) -> Result<String, ApiError> {
+        expand(message, |name| match name {
+            "display_name" => Some("Streamer".into()),
+            "clip_url" => Some("https://clips.twitch.tv/x".into()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        assert_eq!(
+            fill("Hi {display_name}, we watched {clip_url}.").unwrap(),
+            "Hi Streamer, we watched https://clips.twitch.tv/x."
+        );
+        assert_eq!(fill("no placeholders").unwrap(), "no placeholders");
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(fill("{{display_name}}").unwrap(), "{display_name}");
+        assert_eq!(fill("{{{display_name}}}").unwrap(), "{Streamer}");
+        assert_eq!(fill("}}{{").unwrap(), "}{");
+    }
+
+    #[test]
+    fn rejects_unmatched_braces() {
+        for message in ["{display_name", "a { b", "a } b", "{display_name}}", "}"] {
+            let err = fill(message).unwrap_err();
+            assert_eq!(err.status, axum::http::StatusCode::BAD_REQUEST, "{message:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        let err = fill("{nope}").unwrap_err();
+        assert!(err.message.contains("unknown placeholder {nope}"), "{}", err.message);
+    }
+
+    #[test]
+    fn validates_messages() {
+        assert!(validate_message("Hi {display_name} from {reviewer}, you are {status}").is_ok());
+        assert!(validate_message("{username} {clip_url}").is_ok());
+        assert!(validate_message("{nope}").is_err());
+        assert!(validate_message("  ").is_err());
+        assert!(validate_message(&"a".repeat(MAX_MESSAGE_LENGTH)).is_ok());
+        assert!(validate_message(&"a".repeat(MAX_MESSAGE_LENGTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use diesel::prelude::Insertable;
+use diesel::query_dsl::methods::{FindDsl, OrderDsl, SelectDsl};
+use diesel::{ExpressionMethods, OptionalExtension, SelectableHelper};
+use diesel_async::RunQueryDsl;
+
+use super::auth::{hash_api_token, role_has_permission, RequirePermission, API_TOKEN_PREFIX};
+use super::error::ApiError;
+use super::permissions::{scopes, Permission};
+use crate::config::random_secret;
+use crate::database::enums::TwitchAccountType;
+use crate::database::schema;
+use crate::database::types::ApiToken;
+use crate::global::Global;
+
+pub fn routes() -> Router<Arc<Global>> {
+    Router::new()
+        .route("/", get(get_tokens).post(create_token))
+        .route("/:id", delete(revoke_token))
+}
+
+/// GET /tokens
+/// Get all api tokens
+/// Scope: tokens:manage
+async fn get_tokens(
+    State(global): State<Arc<Global>>,
+    _: RequirePermission<scopes::TokensManage>,
+) -> Result<Json<Vec<ApiToken>>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let tokens = schema::api_tokens::table
+        .order(schema::api_tokens::dsl::created_at)
+        .select(ApiToken::as_select())
+        .load(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch api tokens: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(tokens))
+}
+
+#[derive(serde::Deserialize)]
+struct CreateTokenRequest {
+    name: String,
+    scopes: Vec<String>,
+    expires_in_secs: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+struct CreateTokenResponse {
+    /// The token itself, this is the only time it is ever returned.
+    token: String,
+    api_token: ApiToken,
+}
+
+#[derive(Insertable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::api_tokens)]
+struct InsertApiToken<'a> {
+    name: &'a str,
+    token_hash: &'a str,
+    scopes: &'a [String],
+    twitch_user_id: i32,
+    twitch_username: &'a str,
+    twitch_display_name: &'a str,
+    twitch_profile_image_url: &'a str,
+    twitch_account_type: TwitchAccountType,
+    follow_count: i32,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// POST /tokens
+/// Create an api token acting on behalf of the current user
+/// Scope: tokens:manage
+async fn create_token(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, role, _): RequirePermission<scopes::TokensManage>,
+    Json(body): Json<CreateTokenRequest>,
+) -> Result<Json<CreateTokenResponse>, ApiError> {
+    if body.name.is_empty() || body.name.len() > 100 {
+        return Err(ApiError::bad_request("name must be between 1 and 100 characters"));
+    }
+
+    for scope in &body.scopes {
+        let Some(permission) = Permission::from_name(scope) else {
+            return Err(ApiError::bad_request(format!("unknown scope {scope}")));
+        };
+
+        // A token made with another token can not do more than that one.
+        if !role_has_permission(&user, role, permission) {
+            return Err(ApiError::bad_request(format!("you do not have the scope {scope}")));
+        }
+    }
+
+    let expires_at = match body.expires_in_secs {
+        Some(secs) if secs <= 0 => return Err(ApiError::bad_request("expires_in_secs must be greater than 0")),
+        Some(secs) => Some(
+            chrono::TimeDelta::try_seconds(secs)
+                .and_then(|lifetime| chrono::Utc::now().checked_add_signed(lifetime))
+                .ok_or_else(|| ApiError::bad_request("expires_in_secs is too large"))?,
+        ),
+        None => None,
+    };
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let token = format!("{API_TOKEN_PREFIX}{}", random_secret());
+
+    let api_token = diesel::insert_into(schema::api_tokens::table)
+        .values(InsertApiToken {
+            name: &body.name,
+            token_hash: &hash_api_token(&token),
+            scopes: &body.scopes,
+            twitch_user_id: user.twitch_user_id,
+            twitch_username: &user.twitch_username,
+            twitch_display_name: &user.twitch_display_name,
+            twitch_profile_image_url: &user.twitch_profile_image_url,
+            twitch_account_type: user.twitch_account_type,
+            follow_count: user.follow_count,
+            expires_at,
+        })
+        .returning(ApiToken::as_returning())
+        .get_result(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to insert api token: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(CreateTokenResponse { token, api_token }))
+}
+
+/// DELETE /tokens/:id
+/// Revoke an api token
+/// Scope: tokens:manage
+async fn revoke_token(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    _: RequirePermission<scopes::TokensManage>,
+) -> Result<Json<ApiToken>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let api_token = diesel::update(schema::api_tokens::dsl::api_tokens.find(id))
+        .filter(schema::api_tokens::dsl::revoked_at.is_null())
+        .set(schema::api_tokens::dsl::revoked_at.eq(chrono::Utc::now()))
+        .returning(ApiToken::as_returning())
+        .get_result(&mut db)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to revoke api token: {err}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(ApiError::not_found)?;
+
+    Ok(Json(api_token))
+}
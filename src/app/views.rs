@@ -0,0 +1,286 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use diesel::prelude::{AsChangeset, Insertable};
+use diesel::query_dsl::methods::{FilterDsl, FindDsl, OrderDsl, SelectDsl};
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, SelectableHelper};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use super::applications::{
+    filter_applications, list_applications, ApplicationsSort, GetApplicationsRequest, GetApplicationsResponse, PageRequest,
+};
+use super::auth::{role_has_permission, RequirePermission, User};
+use super::error::ApiError;
+use super::permissions::{scopes, Permission};
+use crate::database::enums::UserRole;
+use crate::database::schema;
+use crate::database::types::SavedView;
+use crate::global::Global;
+
+pub fn routes() -> Router<Arc<Global>> {
+    Router::new()
+        .route("/", get(get_views).post(create_view))
+        .route("/:id", get(get_view).put(update_view).delete(delete_view))
+        .route("/:id/applications", get(run_view))
+}
+
+/// What a view stores: the filters and sort accepted by GET /applications.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ViewQuery {
+    filters: GetApplicationsRequest,
+    #[serde(flatten)]
+    sort: ApplicationsSort,
+}
+
+impl ViewQuery {
+    fn from_view(view: &SavedView) -> Result<Self, ApiError> {
+        serde_json::from_value(view.query.clone()).map_err(|err| {
+            tracing::error!("Failed to decode saved view {}: {err}", view.id);
+            ApiError::internal_server_error()
+        })
+    }
+
+    /// Make sure running the view would not fail on bad filters.
+    fn validate(&self, twitch_user_id: i32) -> Result<(), ApiError> {
+        filter_applications(&self.filters, twitch_user_id)?;
+        self.sort.resolve(&self.filters)?;
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SaveViewRequest {
+    name: String,
+    #[serde(default)]
+    shared: bool,
+    query: ViewQuery,
+}
+
+impl SaveViewRequest {
+    fn validate(&self, user: &User) -> Result<serde_json::Value, ApiError> {
+        if self.name.is_empty() || self.name.len() > 100 {
+            return Err(ApiError::bad_request("name must be between 1 and 100 characters"));
+        }
+
+        self.query.validate(user.twitch_user_id)?;
+
+        serde_json::to_value(&self.query).map_err(|err| {
+            tracing::error!("Failed to encode saved view: {err}");
+            ApiError::internal_server_error()
+        })
+    }
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = schema::saved_views)]
+struct InsertSavedView<'a> {
+    name: &'a str,
+    twitch_user_id: i32,
+    shared: bool,
+    query: serde_json::Value,
+}
+
+/// Fetch a view the user can see, their own or a shared one.
+async fn fetch_view(conn: &mut AsyncPgConnection, id: i32, user: &User) -> Result<SavedView, ApiError> {
+    let view = schema::saved_views::table
+        .find(id)
+        .select(SavedView::as_select())
+        .get_result(conn)
+        .await
+        .optional()
+        .map_err(|err| {
+            tracing::error!("Failed to fetch saved view: {err}");
+            ApiError::internal_server_error()
+        })?
+        .ok_or_else(ApiError::not_found)?;
+
+    if view.twitch_user_id != user.twitch_user_id && !view.shared {
+        return Err(ApiError::not_found());
+    }
+
+    Ok(view)
+}
+
+/// Views can be changed by whoever created them, shared views also by admins.
+fn can_edit(view: &SavedView, user: &User, role: UserRole) -> bool {
+    view.twitch_user_id == user.twitch_user_id || (view.shared && role_has_permission(user, role, Permission::ViewsManage))
+}
+
+/// GET /applications/views
+/// Get the views of the current user and every shared view
+/// Scope: applications:read
+async fn get_views(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsRead>,
+) -> Result<Json<Vec<SavedView>>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let views = schema::saved_views::table
+        .filter(
+            schema::saved_views::dsl::twitch_user_id
+                .eq(user.twitch_user_id)
+                .or(schema::saved_views::dsl::shared),
+        )
+        .order((schema::saved_views::dsl::name, schema::saved_views::dsl::id))
+        .select(SavedView::as_select())
+        .load(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to fetch saved views: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(views))
+}
+
+/// POST /applications/views
+/// Save a view for the current user, optionally sharing it with everyone
+/// Scope: applications:read
+async fn create_view(
+    State(global): State<Arc<Global>>,
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsRead>,
+    Json(body): Json<SaveViewRequest>,
+) -> Result<Json<SavedView>, ApiError> {
+    let query = body.validate(&user)?;
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let view = diesel::insert_into(schema::saved_views::table)
+        .values(InsertSavedView {
+            name: &body.name,
+            twitch_user_id: user.twitch_user_id,
+            shared: body.shared,
+            query,
+        })
+        .returning(SavedView::as_returning())
+        .get_result(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to insert saved view: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(view))
+}
+
+/// GET /applications/views/:id
+/// Get a view by id
+/// Scope: applications:read
+async fn get_view(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsRead>,
+) -> Result<Json<SavedView>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    fetch_view(&mut db, id, &user).await.map(Json)
+}
+
+/// PUT /applications/views/:id
+/// Replace the name, sharing and query of a view
+/// Scope: applications:read (own view) or admin role (shared view)
+async fn update_view(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    RequirePermission(user, role, _): RequirePermission<scopes::ApplicationsRead>,
+    Json(body): Json<SaveViewRequest>,
+) -> Result<Json<SavedView>, ApiError> {
+    let query = body.validate(&user)?;
+
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let view = fetch_view(&mut db, id, &user).await?;
+    if !can_edit(&view, &user, role) {
+        return Err(ApiError::forbidden("you can not change this view"));
+    }
+
+    let view = diesel::update(schema::saved_views::table.find(id))
+        .set((
+            InsertSavedView {
+                name: &body.name,
+                // Keep the original owner when an admin edits a shared view.
+                twitch_user_id: view.twitch_user_id,
+                shared: body.shared,
+                query,
+            },
+            schema::saved_views::dsl::updated_at.eq(chrono::Utc::now()),
+        ))
+        .returning(SavedView::as_returning())
+        .get_result(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to update saved view: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(view))
+}
+
+/// DELETE /applications/views/:id
+/// Delete a view
+/// Scope: applications:read (own view) or admin role (shared view)
+async fn delete_view(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    RequirePermission(user, role, _): RequirePermission<scopes::ApplicationsRead>,
+) -> Result<Json<SavedView>, ApiError> {
+    let mut db = global.database.get().await.map_err(|err| {
+        tracing::error!("Failed to get database: {err}");
+        ApiError::internal_server_error()
+    })?;
+
+    let view = fetch_view(&mut db, id, &user).await?;
+    if !can_edit(&view, &user, role) {
+        return Err(ApiError::forbidden("you can not change this view"));
+    }
+
+    diesel::delete(schema::saved_views::table.find(id))
+        .execute(&mut db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to delete saved view: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+    Ok(Json(view))
+}
+
+/// GET /applications/views/:id/applications
+/// Get a page of the applications matching a view
+/// Scope: applications:read
+async fn run_view(
+    State(global): State<Arc<Global>>,
+    Path(id): Path<i32>,
+    RequirePermission(user, ..): RequirePermission<scopes::ApplicationsRead>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<GetApplicationsResponse>, ApiError> {
+    let view = {
+        let mut db = global.database.get().await.map_err(|err| {
+            tracing::error!("Failed to get database: {err}");
+            ApiError::internal_server_error()
+        })?;
+
+        fetch_view(&mut db, id, &user).await?
+    };
+
+    let query = ViewQuery::from_view(&view)?;
+
+    list_applications(&global, user.twitch_user_id, &query.filters, &query.sort, &page)
+        .await
+        .map(Json)
+}
@@ -11,13 +11,37 @@ pub struct Config {
     pub level: String,
     #[default(None)]
     pub telemetry_bind: Option<SocketAddr>,
+    /// Twitch ids that are granted the owner role on startup if they do not
+    /// have a role yet.
     #[default(vec![])]
     pub admin_twitch_ids: Vec<i32>,
     #[default(SocketAddr::from(([0, 0, 0, 0], 3000)))]
     pub http_bind: SocketAddr,
     pub twitch: TwitchConfig,
-    #[default(random_secret())]
-    pub jwt_secret: String,
+    /// The secret session tokens were signed with before key rotation was
+    /// supported. It is added to the keyring with the key id `default`.
+    #[default(std::env::var("JWT_SECRET").ok())]
+    pub jwt_secret: Option<String>,
+    /// Every key session tokens can be verified with.
+    #[default(vec![])]
+    pub jwt_keys: Vec<JwtKey>,
+    /// The key id new session tokens are signed with, defaults to the first
+    /// configured key.
+    #[default(None)]
+    pub jwt_signing_kid: Option<String>,
+    /// Allows starting without a persistent jwt key, tokens are then signed
+    /// with a random key and do not survive a restart.
+    #[default(false)]
+    pub dev_mode: bool,
+    /// How long a session token is valid for.
+    #[default(60 * 60 * 24 * 7)]
+    pub jwt_lifetime_secs: i64,
+    /// How long before expiry a session token may be refreshed.
+    #[default(60 * 60 * 24)]
+    pub jwt_refresh_window_secs: i64,
+    /// How long an admin can view the api as another user.
+    #[default(60 * 15)]
+    pub impersonation_lifetime_secs: i64,
     #[default(env_or_default("PUBLIC_API_URL", "https://onlyfangs.gay/api"))]
     pub api_url: String,
     #[default(env_or_default("PUBLIC_APP_URL", "https://onlyfangs.gay"))]
@@ -26,14 +50,33 @@ pub struct Config {
     pub vite_dist_dir: String,
 }
 
-#[derive(Default, serde::Deserialize, Debug)]
+#[derive(serde::Deserialize, Debug)]
+pub struct JwtKey {
+    pub kid: String,
+    pub secret: String,
+}
+
+#[derive(smart_default::SmartDefault, serde::Deserialize, Debug)]
+#[serde(default)]
 pub struct TwitchConfig {
     pub client_id: String,
     pub client_secret: String,
     pub redirect_uri: String,
+    #[default(vec![])]
+    pub scopes: Vec<String>,
+    /// How long a login started with GET /login can take to complete.
+    #[default(600)]
+    pub login_state_ttl_secs: i64,
+    /// The base url of the Twitch OAuth2 endpoints, overridable so we can
+    /// point the login flow at a mock server.
+    #[default("https://id.twitch.tv/oauth2".into())]
+    pub oauth_url: String,
+    /// The base url of the Twitch Helix API.
+    #[default("https://api.twitch.tv/helix".into())]
+    pub helix_url: String,
 }
 
-fn random_secret() -> String {
+pub fn random_secret() -> String {
     let mut rng = rand::thread_rng();
     // 32 bytes of random data
     let mut bytes = [0u8; 32];
@@ -30,6 +30,18 @@ macro_rules! impl_enum {
                 }
             }
 
+            impl ::std::fmt::Display for $enum {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    let value: &[u8] = match self {
+                        $(
+                            $enum::$variant => $value,
+                        )*
+                    };
+
+                    f.write_str(::std::str::from_utf8(value).map_err(|_| ::std::fmt::Error)?)
+                }
+            }
+
             impl ::diesel::deserialize::FromSql<$sql_type, ::diesel::pg::Pg> for $enum {
                 fn from_sql(bytes: <::diesel::pg::Pg as ::diesel::backend::Backend>::RawValue<'_>) -> ::diesel::deserialize::Result<Self> {
                     match bytes.as_bytes() {
@@ -49,10 +61,102 @@ impl_enum!(ApplicationStatus, super::schema::sql_types::ApplicationStatus, {
     Approved => b"approved",
     Maybe => b"maybe",
     Rejected => b"rejected",
+    In => b"in",
+    Withdrawn => b"withdrawn",
 });
 
+impl ApplicationStatus {
+    /// Whether the review of an application in this status is over.
+    pub const fn is_completed(self) -> bool {
+        matches!(
+            self,
+            ApplicationStatus::Approved | ApplicationStatus::Rejected | ApplicationStatus::In | ApplicationStatus::Withdrawn
+        )
+    }
+
+    /// The lifecycle of an application: pending -> maybe -> approved/rejected
+    /// -> in. Applicants can withdraw until they are in, and finished
+    /// applications can be reopened by moving them back to pending.
+    pub const fn can_transition_to(self, next: ApplicationStatus) -> bool {
+        use ApplicationStatus::*;
+
+        matches!(
+            (self, next),
+            (Pending, Maybe | Approved | Rejected | Withdrawn)
+                | (Maybe, Approved | Rejected | Withdrawn)
+                | (Approved, In | Withdrawn | Pending)
+                | (Rejected | Withdrawn, Pending)
+        )
+    }
+}
+
 impl_enum!(TwitchAccountType, super::schema::sql_types::TwitchAccountType, {
     Pleb => b"pleb",
     Affiliate => b"affiliate",
     Partner => b"partner",
 });
+
+impl_enum!(CommentVisibility, super::schema::sql_types::CommentVisibility, {
+    Public => b"public",
+    Internal => b"internal",
+});
+
+/// Comments are public unless they are marked internal.
+impl Default for CommentVisibility {
+    fn default() -> Self {
+        CommentVisibility::Public
+    }
+}
+
+impl_enum!(UserRole, super::schema::sql_types::UserRole, {
+    Owner => b"owner",
+    Admin => b"admin",
+    Reviewer => b"reviewer",
+    Viewer => b"viewer",
+});
+
+impl UserRole {
+    const fn rank(self) -> u8 {
+        match self {
+            UserRole::Owner => 3,
+            UserRole::Admin => 2,
+            UserRole::Reviewer => 1,
+            UserRole::Viewer => 0,
+        }
+    }
+
+    /// Whether this role grants everything `other` does.
+    pub const fn includes(self, other: UserRole) -> bool {
+        self.rank() >= other.rank()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApplicationStatus::{self, *};
+
+    const ALL: [ApplicationStatus; 6] = [Pending, Maybe, Approved, Rejected, In, Withdrawn];
+
+    #[test]
+    fn application_lifecycle() {
+        let allowed = [
+            (Pending, &[Maybe, Approved, Rejected, Withdrawn][..]),
+            (Maybe, &[Approved, Rejected, Withdrawn]),
+            (Approved, &[In, Withdrawn, Pending]),
+            (Rejected, &[Pending]),
+            (In, &[]),
+            (Withdrawn, &[Pending]),
+        ];
+
+        for (from, to) in allowed {
+            for next in ALL {
+                assert_eq!(
+                    from.can_transition_to(next),
+                    to.contains(&next),
+                    "{from} -> {next} should {}be allowed",
+                    if to.contains(&next) { "" } else { "not " },
+                );
+            }
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use chrono::{DateTime, Utc};
 use diesel::pg::Pg;
 use diesel::prelude::Queryable;
-use diesel::query_dsl::methods::{FindDsl, SelectDsl};
-use diesel::{OptionalExtension, Selectable, SelectableHelper};
+use diesel::query_dsl::methods::{FilterDsl, FindDsl, SelectDsl};
+use diesel::{ExpressionMethods, OptionalExtension, Selectable, SelectableHelper};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 
-use super::enums::{ApplicationStatus, TwitchAccountType};
+use super::enums::{ApplicationStatus, CommentVisibility, TwitchAccountType, UserRole};
 use super::schema;
 
 #[derive(Debug, serde::Serialize, Selectable, Queryable)]
@@ -29,6 +29,17 @@ pub struct Application {
 }
 
 impl Application {
+    pub async fn fetch_by_twitch_id(conn: &mut AsyncPgConnection, twitch_id: i32) -> anyhow::Result<Option<Self>> {
+        let application: Option<Self> = schema::applications::dsl::applications
+            .filter(schema::applications::dsl::twitch_id.eq(twitch_id))
+            .select(Application::as_select())
+            .get_result(conn)
+            .await
+            .optional()?;
+
+        Ok(application)
+    }
+
     pub async fn fetch_by_id(conn: &mut AsyncPgConnection, id: i32) -> anyhow::Result<Option<Self>> {
         let application: Option<Self> = schema::applications::dsl::applications
             .find(id)
@@ -54,4 +65,116 @@ pub struct ApplicationComment {
     pub twitch_display_name: String,
     pub twitch_profile_image_url: String,
     pub created_at: DateTime<Utc>,
+    pub visibility: CommentVisibility,
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Deleted comments are kept as a tombstone with an empty comment.
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<i32>,
+    /// The top level comment this is a reply to.
+    pub parent_id: Option<i32>,
+    /// The comment rendered from Markdown, safe to show as is.
+    pub comment_html: String,
+}
+
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = schema::login_states)]
+#[diesel(primary_key(state))]
+#[diesel(check_for_backend(Pg))]
+pub struct LoginState {
+    pub pkce_verifier: String,
+    pub redirect_uri: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, serde::Serialize, Queryable, Selectable)]
+#[diesel(table_name = schema::user_roles)]
+#[diesel(primary_key(twitch_user_id))]
+#[diesel(check_for_backend(Pg))]
+pub struct UserRoleGrant {
+    pub twitch_user_id: i32,
+    pub role: UserRole,
+    pub granted_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// As of the last time they logged in, used to resolve @mentions.
+    pub twitch_username: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, Queryable, Selectable)]
+#[diesel(table_name = schema::api_tokens)]
+#[diesel(primary_key(id))]
+#[diesel(check_for_backend(Pg))]
+pub struct ApiToken {
+    pub id: i32,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub twitch_user_id: i32,
+    pub twitch_username: String,
+    pub twitch_display_name: String,
+    pub twitch_profile_image_url: String,
+    pub twitch_account_type: TwitchAccountType,
+    pub follow_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, serde::Serialize, Queryable, Selectable)]
+#[diesel(table_name = schema::saved_views)]
+#[diesel(primary_key(id))]
+#[diesel(check_for_backend(Pg))]
+pub struct SavedView {
+    pub id: i32,
+    pub name: String,
+    pub twitch_user_id: i32,
+    pub shared: bool,
+    pub query: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, serde::Serialize, Queryable, Selectable)]
+#[diesel(table_name = schema::application_status_events)]
+#[diesel(primary_key(id))]
+#[diesel(check_for_backend(Pg))]
+pub struct ApplicationStatusEvent {
+    pub id: i32,
+    pub application_id: i32,
+    pub from_status: ApplicationStatus,
+    pub to_status: ApplicationStatus,
+    pub actor_twitch_user_id: i32,
+    pub actor_twitch_username: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub reason_code: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, Queryable, Selectable)]
+#[diesel(table_name = schema::message_templates)]
+#[diesel(primary_key(id))]
+#[diesel(check_for_backend(Pg))]
+pub struct MessageTemplate {
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub body: String,
+    pub created_by: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, serde::Serialize, Queryable, Selectable)]
+#[diesel(table_name = schema::comment_revisions)]
+#[diesel(primary_key(id))]
+#[diesel(check_for_backend(Pg))]
+pub struct CommentRevision {
+    pub id: i32,
+    pub comment_id: i32,
+    /// The text of the comment before it was edited or deleted.
+    pub comment: String,
+    pub twitch_user_id: i32,
+    pub twitch_username: String,
+    pub created_at: DateTime<Utc>,
 }
@@ -1,9 +1,19 @@
 use diesel_async::pooled_connection::bb8;
 use diesel_async::AsyncPgConnection;
 
+use crate::cache::TtlCache;
 use crate::config::Config;
+use crate::database::enums::UserRole;
+use crate::jwt::JwtKeys;
+use crate::twitch::TwitchClient;
 
 pub struct Global {
     pub config: Config,
     pub database: bb8::Pool<AsyncPgConnection>,
+    pub twitch: TwitchClient,
+    pub jwt_keys: JwtKeys,
+    /// Whether a session id is still active, so we do not hit the database
+    /// for every authenticated request.
+    pub session_cache: TtlCache<String, bool>,
+    pub role_cache: TtlCache<i32, Option<UserRole>>,
 }
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::config::{random_secret, Config};
+
+/// The key id used for the legacy `jwt_secret` and for tokens that were
+/// issued without a key id.
+const DEFAULT_KID: &str = "default";
+
+/// The keys session tokens are signed and verified with.
+pub struct JwtKeys {
+    signing_kid: String,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, DecodingKey>,
+}
+
+impl JwtKeys {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut secrets: Vec<(&str, String)> = Vec::new();
+
+        for key in &config.jwt_keys {
+            anyhow::ensure!(!key.secret.is_empty(), "jwt key {} has an empty secret", key.kid);
+            anyhow::ensure!(
+                secrets.iter().all(|(kid, _)| *kid != key.kid),
+                "jwt key {} is configured twice",
+                key.kid
+            );
+            secrets.push((&key.kid, key.secret.clone()));
+        }
+
+        if let Some(secret) = config.jwt_secret.as_deref().filter(|secret| !secret.is_empty()) {
+            anyhow::ensure!(
+                secrets.iter().all(|(kid, _)| *kid != DEFAULT_KID),
+                "jwt_secret conflicts with the jwt key {DEFAULT_KID}"
+            );
+            secrets.push((DEFAULT_KID, secret.to_owned()));
+        }
+
+        if secrets.is_empty() {
+            anyhow::ensure!(
+                config.dev_mode,
+                "no jwt key configured, set jwt_keys or jwt_secret (or enable dev_mode)"
+            );
+
+            tracing::warn!("no jwt key configured, using a random key. sessions will not survive a restart");
+            secrets.push((DEFAULT_KID, random_secret()));
+        }
+
+        let signing_kid = config.jwt_signing_kid.as_deref().unwrap_or(secrets[0].0).to_owned();
+
+        let encoding_key = secrets
+            .iter()
+            .find(|(kid, _)| *kid == signing_kid)
+            .map(|(_, secret)| EncodingKey::from_secret(secret.as_bytes()))
+            .with_context(|| format!("jwt signing key {signing_kid} is not configured"))?;
+
+        let decoding_keys = secrets
+            .iter()
+            .map(|(kid, secret)| (kid.to_string(), DecodingKey::from_secret(secret.as_bytes())))
+            .collect();
+
+        Ok(Self {
+            signing_kid,
+            encoding_key,
+            decoding_keys,
+        })
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(self.signing_kid.clone());
+        jsonwebtoken::encode(&header, claims, &self.encoding_key)
+    }
+
+    /// Verify a token with the key named by its `kid` header.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str, validation: &Validation) -> anyhow::Result<T> {
+        let header = jsonwebtoken::decode_header(token).context("decode header")?;
+        let kid = header.kid.as_deref().unwrap_or(DEFAULT_KID);
+        let key = self.decoding_keys.get(kid).with_context(|| format!("unknown key id {kid}"))?;
+
+        Ok(jsonwebtoken::decode(token, key, validation).context("decode token")?.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JwtKey;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct TestClaims {
+        sub: String,
+        exp: i64,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "1234".into(),
+            exp: chrono::Utc::now().timestamp() + 60,
+        }
+    }
+
+    fn config(kids: &[&str], signing_kid: Option<&str>, jwt_secret: Option<&str>) -> Config {
+        Config {
+            jwt_keys: kids
+                .iter()
+                .map(|kid| JwtKey {
+                    kid: kid.to_string(),
+                    secret: format!("secret-{kid}"),
+                })
+                .collect(),
+            jwt_signing_kid: signing_kid.map(Into::into),
+            jwt_secret: jwt_secret.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    fn kid(token: &str) -> Option<String> {
+        jsonwebtoken::decode_header(token).unwrap().kid
+    }
+
+    #[test]
+    fn signs_with_the_configured_key() {
+        let keys = JwtKeys::from_config(&config(&["old", "new"], Some("new"), None)).unwrap();
+        let token = keys.sign(&claims()).unwrap();
+
+        assert_eq!(kid(&token).as_deref(), Some("new"));
+        assert_eq!(
+            keys.decode::<TestClaims>(&token, &Validation::new(Algorithm::HS256))
+                .unwrap()
+                .sub,
+            "1234"
+        );
+    }
+
+    #[test]
+    fn signs_with_the_first_key_by_default() {
+        let keys = JwtKeys::from_config(&config(&["a", "b"], None, Some("legacy"))).unwrap();
+        assert_eq!(kid(&keys.sign(&claims()).unwrap()).as_deref(), Some("a"));
+
+        let keys = JwtKeys::from_config(&config(&[], None, Some("legacy"))).unwrap();
+        assert_eq!(kid(&keys.sign(&claims()).unwrap()).as_deref(), Some(DEFAULT_KID));
+    }
+
+    #[test]
+    fn rejects_an_unconfigured_signing_key() {
+        assert!(JwtKeys::from_config(&config(&["a"], Some("b"), None)).is_err());
+        assert!(JwtKeys::from_config(&config(&["a", "a"], None, None)).is_err());
+        assert!(JwtKeys::from_config(&config(&["default"], None, Some("legacy"))).is_err());
+    }
+
+    #[test]
+    fn decodes_tokens_of_retired_keys() {
+        let old = JwtKeys::from_config(&config(&["old"], None, None)).unwrap();
+        let token = old.sign(&claims()).unwrap();
+
+        // The old key is still configured but no longer signs.
+        let rotated = JwtKeys::from_config(&config(&["new", "old"], Some("new"), None)).unwrap();
+        assert_eq!(
+            rotated
+                .decode::<TestClaims>(&token, &Validation::new(Algorithm::HS256))
+                .unwrap()
+                .sub,
+            "1234"
+        );
+        assert_eq!(kid(&rotated.sign(&claims()).unwrap()).as_deref(), Some("new"));
+
+        // Once it is removed its tokens stop working.
+        let removed = JwtKeys::from_config(&config(&["new"], None, None)).unwrap();
+        assert!(removed
+            .decode::<TestClaims>(&token, &Validation::new(Algorithm::HS256))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_key_ids() {
+        let keys = JwtKeys::from_config(&config(&["a"], None, None)).unwrap();
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("unknown".into());
+        let token = jsonwebtoken::encode(&header, &claims(), &EncodingKey::from_secret(b"secret-a")).unwrap();
+
+        let err = keys
+            .decode::<TestClaims>(&token, &Validation::new(Algorithm::HS256))
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown key id unknown"), "{err}");
+    }
+
+    #[test]
+    fn tokens_without_a_key_id_use_the_legacy_secret() {
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims(),
+            &EncodingKey::from_secret(b"legacy"),
+        )
+        .unwrap();
+
+        let keys = JwtKeys::from_config(&config(&["a"], None, Some("legacy"))).unwrap();
+        assert!(keys.decode::<TestClaims>(&token, &Validation::new(Algorithm::HS256)).is_ok());
+
+        let keys = JwtKeys::from_config(&config(&["a"], None, None)).unwrap();
+        assert!(keys.decode::<TestClaims>(&token, &Validation::new(Algorithm::HS256)).is_err());
+    }
+}
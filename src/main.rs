@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use database::schema::health_check;
+use database::schema::{health_check, user_roles};
 use diesel::query_dsl::methods::FindDsl;
 use diesel::ExpressionMethods;
 use diesel_async::{AsyncConnection, RunQueryDsl};
@@ -10,10 +10,13 @@ use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::Layer;
 
 mod app;
+mod cache;
 mod config;
 mod database;
 mod global;
+mod jwt;
 mod migrations;
+mod twitch;
 
 impl scuffle_bootstrap::Global for global::Global {
     type Config = config::Config;
@@ -30,6 +33,8 @@ impl scuffle_bootstrap::Global for global::Global {
 
         tracing::info!("starting server.");
 
+        let jwt_keys = jwt::JwtKeys::from_config(&config).context("load jwt keys")?;
+
         let Some(db_url) = config.db_url.as_deref() else {
             anyhow::bail!("DATABASE_URL is not set");
         };
@@ -50,7 +55,20 @@ impl scuffle_bootstrap::Global for global::Global {
 
         tracing::info!("database initialized");
 
-        Ok(Arc::new(Self { config, database }))
+        seed_owners(&database, &config.admin_twitch_ids)
+            .await
+            .context("seed owners")?;
+
+        let twitch = twitch::TwitchClient::new().context("build twitch client")?;
+
+        Ok(Arc::new(Self {
+            config,
+            database,
+            twitch,
+            jwt_keys,
+            session_cache: cache::TtlCache::new(std::time::Duration::from_secs(30)),
+            role_cache: cache::TtlCache::new(std::time::Duration::from_secs(30)),
+        }))
     }
 }
 
@@ -64,6 +82,34 @@ async fn run_migrations(url: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Make sure the configured admins exist as owners so there is always someone
+/// who can manage roles. Existing roles are left untouched.
+async fn seed_owners(
+    database: &diesel_async::pooled_connection::bb8::Pool<diesel_async::AsyncPgConnection>,
+    twitch_ids: &[i32],
+) -> anyhow::Result<()> {
+    let mut conn = database.get().await.context("get database connection")?;
+
+    let owners: Vec<_> = twitch_ids
+        .iter()
+        .map(|id| {
+            (
+                user_roles::dsl::twitch_user_id.eq(*id),
+                user_roles::dsl::role.eq(database::enums::UserRole::Owner),
+            )
+        })
+        .collect();
+
+    diesel::insert_into(user_roles::table)
+        .values(owners)
+        .on_conflict_do_nothing()
+        .execute(&mut conn)
+        .await
+        .context("insert owners")?;
+
+    Ok(())
+}
+
 impl scuffle_signal::SignalConfig for global::Global {
     async fn on_shutdown(self: &Arc<Self>) -> anyhow::Result<()> {
         tracing::info!("shutting down");
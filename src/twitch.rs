@@ -0,0 +1,310 @@
+use anyhow::Context;
+use base64::Engine;
+use reqwest::{StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use crate::config::TwitchConfig;
+use crate::database::enums::TwitchAccountType;
+
+/// A small client for the parts of the Twitch API we need during login.
+pub struct TwitchClient {
+    http: reqwest::Client,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct HelixUser {
+    pub id: String,
+    pub login: String,
+    pub display_name: String,
+    pub profile_image_url: String,
+    pub broadcaster_type: String,
+}
+
+impl HelixUser {
+    pub fn account_type(&self) -> TwitchAccountType {
+        match self.broadcaster_type.as_str() {
+            "partner" => TwitchAccountType::Partner,
+            "affiliate" => TwitchAccountType::Affiliate,
+            _ => TwitchAccountType::Pleb,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct HelixData<T> {
+    data: Vec<T>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct HelixFollowers {
+    total: i32,
+}
+
+impl TwitchClient {
+    pub fn new() -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .context("build http client")?;
+
+        Ok(Self { http })
+    }
+
+    /// The url we redirect the user to so they can authorize our application.
+    pub fn authorize_url(config: &TwitchConfig, state: &str, code_challenge: &str) -> anyhow::Result<Url> {
+        let mut url = Url::parse(&format!("{}/authorize", config.oauth_url)).context("parse oauth url")?;
+
+        url.query_pairs_mut()
+            .append_pair("client_id", &config.client_id)
+            .append_pair("redirect_uri", &config.redirect_uri)
+            .append_pair("response_type", "code")
+            .append_pair("scope", &config.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url)
+    }
+
+    /// The S256 PKCE challenge for a code verifier.
+    pub fn pkce_challenge(code_verifier: &str) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+    }
+
+    /// Exchange an authorization code for a user access token.
+    /// Returns `None` if Twitch rejected the code.
+    pub async fn exchange_code(
+        &self,
+        config: &TwitchConfig,
+        code: &str,
+        code_verifier: &str,
+    ) -> anyhow::Result<Option<TokenResponse>> {
+        let response = self
+            .http
+            .post(format!("{}/token", config.oauth_url))
+            .form(&[
+                ("client_id", config.client_id.as_str()),
+                ("client_secret", config.client_secret.as_str()),
+                ("code", code),
+                ("grant_type", "authorization_code"),
+                ("redirect_uri", config.redirect_uri.as_str()),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .context("send token request")?;
+
+        if response.status() == StatusCode::BAD_REQUEST {
+            return Ok(None);
+        }
+
+        let token = response
+            .error_for_status()
+            .context("token request")?
+            .json()
+            .await
+            .context("decode token response")?;
+
+        Ok(Some(token))
+    }
+
+    /// Fetch the user the access token belongs to.
+    pub async fn get_user(&self, config: &TwitchConfig, access_token: &str) -> anyhow::Result<HelixUser> {
+        let response: HelixData<HelixUser> = self
+            .http
+            .get(format!("{}/users", config.helix_url))
+            .bearer_auth(access_token)
+            .header("Client-Id", &config.client_id)
+            .send()
+            .await
+            .context("send users request")?
+            .error_for_status()
+            .context("users request")?
+            .json()
+            .await
+            .context("decode users response")?;
+
+        response.data.into_iter().next().context("no user returned")
+    }
+
+    /// Fetch the number of followers a broadcaster has.
+    pub async fn get_follower_count(
+        &self,
+        config: &TwitchConfig,
+        access_token: &str,
+        broadcaster_id: &str,
+    ) -> anyhow::Result<i32> {
+        let response: HelixFollowers = self
+            .http
+            .get(format!("{}/channels/followers", config.helix_url))
+            .query(&[("broadcaster_id", broadcaster_id), ("first", "1")])
+            .bearer_auth(access_token)
+            .header("Client-Id", &config.client_id)
+            .send()
+            .await
+            .context("send followers request")?
+            .error_for_status()
+            .context("followers request")?
+            .json()
+            .await
+            .context("decode followers response")?;
+
+        Ok(response.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::extract::Query;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::{get, post};
+    use axum::{Form, Json, Router};
+
+    use super::*;
+
+    fn bearer(headers: &HeaderMap) -> Option<&str> {
+        headers.get("authorization")?.to_str().ok()?.strip_prefix("Bearer ")
+    }
+
+    /// Serve a fake Twitch on a random port and return a config pointing at
+    /// it.
+    async fn mock_twitch() -> TwitchConfig {
+        let app = Router::new()
+            .route(
+                "/oauth2/token",
+                post(|Form(form): Form<HashMap<String, String>>| async move {
+                    let valid = form.get("client_id").map(String::as_str) == Some("client")
+                        && form.get("client_secret").map(String::as_str) == Some("secret")
+                        && form.get("grant_type").map(String::as_str) == Some("authorization_code")
+                        && form.get("redirect_uri").map(String::as_str) == Some("http://app/callback")
+                        && form.get("code").map(String::as_str) == Some("good-code")
+                        && form.get("code_verifier").map(String::as_str) == Some("verifier");
+
+                    if valid {
+                        Ok(Json(serde_json::json!({ "access_token": "access", "token_type": "bearer" })))
+                    } else {
+                        Err(StatusCode::BAD_REQUEST)
+                    }
+                }),
+            )
+            .route(
+                "/helix/users",
+                get(|headers: HeaderMap| async move {
+                    if bearer(&headers) != Some("access") || headers.get("client-id").is_none() {
+                        return Err(StatusCode::UNAUTHORIZED);
+                    }
+
+                    Ok(Json(serde_json::json!({
+                        "data": [{
+                            "id": "1234",
+                            "login": "streamer",
+                            "display_name": "Streamer",
+                            "profile_image_url": "https://example.com/streamer.png",
+                            "broadcaster_type": "affiliate",
+                        }],
+                    })))
+                }),
+            )
+            .route(
+                "/helix/channels/followers",
+                get(
+                    |headers: HeaderMap, Query(query): Query<HashMap<String, String>>| async move {
+                        if bearer(&headers) != Some("access")
+                            || query.get("broadcaster_id").map(String::as_str) != Some("1234")
+                        {
+                            return Err(StatusCode::UNAUTHORIZED);
+                        }
+
+                        Ok(Json(serde_json::json!({ "total": 42, "data": [] })))
+                    },
+                ),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        TwitchConfig {
+            client_id: "client".into(),
+            client_secret: "secret".into(),
+            redirect_uri: "http://app/callback".into(),
+            scopes: vec!["user:read:email".into(), "moderator:read:followers".into()],
+            oauth_url: format!("http://{addr}/oauth2"),
+            helix_url: format!("http://{addr}/helix"),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn authorize_url() {
+        let config = mock_twitch().await;
+        let url = TwitchClient::authorize_url(&config, "state", &TwitchClient::pkce_challenge("verifier")).unwrap();
+
+        assert!(url.as_str().starts_with(&format!("{}/authorize?", config.oauth_url)));
+
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["client_id"], "client");
+        assert_eq!(query["redirect_uri"], "http://app/callback");
+        assert_eq!(query["response_type"], "code");
+        assert_eq!(query["scope"], "user:read:email moderator:read:followers");
+        assert_eq!(query["state"], "state");
+        assert_eq!(query["code_challenge"], TwitchClient::pkce_challenge("verifier"));
+        assert_eq!(query["code_challenge_method"], "S256");
+    }
+
+    #[tokio::test]
+    async fn login_flow() {
+        let config = mock_twitch().await;
+        let client = TwitchClient::new().unwrap();
+
+        assert!(client.exchange_code(&config, "bad-code", "verifier").await.unwrap().is_none());
+
+        let token = client
+            .exchange_code(&config, "good-code", "verifier")
+            .await
+            .unwrap()
+            .expect("code should be accepted");
+        assert_eq!(token.access_token, "access");
+
+        let user = client.get_user(&config, &token.access_token).await.unwrap();
+        assert_eq!(user.id, "1234");
+        assert_eq!(user.login, "streamer");
+        assert_eq!(user.display_name, "Streamer");
+        assert_eq!(user.account_type(), TwitchAccountType::Affiliate);
+
+        let follow_count = client
+            .get_follower_count(&config, &token.access_token, &user.id)
+            .await
+            .unwrap();
+        assert_eq!(follow_count, 42);
+
+        assert!(client.get_user(&config, "wrong").await.is_err());
+    }
+
+    #[test]
+    fn account_type() {
+        for (broadcaster_type, account_type) in [
+            ("partner", TwitchAccountType::Partner),
+            ("affiliate", TwitchAccountType::Affiliate),
+            ("", TwitchAccountType::Pleb),
+            ("something_new", TwitchAccountType::Pleb),
+        ] {
+            let user = HelixUser {
+                id: "1".into(),
+                login: "user".into(),
+                display_name: "User".into(),
+                profile_image_url: String::new(),
+                broadcaster_type: broadcaster_type.into(),
+            };
+
+            assert_eq!(user.account_type(), account_type, "{broadcaster_type:?}");
+        }
+    }
+}
@@ -11,6 +11,8 @@ export enum ApplicationStatus {
   APPROVED = 'approved',
   REJECTED = 'rejected',
   MAYBE = 'maybe',
+  IN = 'in',
+  WITHDRAWN = 'withdrawn',
 }
 
 export enum TwitchAccountType {
@@ -19,12 +21,20 @@ export enum TwitchAccountType {
   PARTNER = 'partner',
 }
 
+export enum CommentVisibility {
+  PUBLIC = 'public',
+  INTERNAL = 'internal',
+}
+
 export interface AddCommentRequest {
   comment: string;
+  visibility?: CommentVisibility;
+  parent_id?: number;
 }
 
 export interface AddCommentResponse {
   comment_id: number;
+  mentions: string[];
 }
 
 export interface Application {
@@ -41,6 +51,7 @@ export interface Application {
   created_at: string;
   updated_at: string;
   completed_at: string | null;
+  comment_count: number;
 }
 
 export interface ApplicationComment {
@@ -52,4 +63,20 @@ export interface ApplicationComment {
   twitch_display_name: string;
   twitch_profile_image_url: string;
   created_at: string;
+  visibility: CommentVisibility;
+  edited_at: string | null;
+  deleted_at: string | null;
+  deleted_by: number | null;
+  parent_id: number | null;
+  comment_html: string;
+}
+
+export interface GetCommentsResponse {
+  comments: ApplicationComment[];
+  next_cursor: string | null;
+}
+
+export interface GetMentionsResponse {
+  comments: ApplicationComment[];
+  next_cursor: string | null;
 }